@@ -0,0 +1,20 @@
+//! Feeds arbitrary bytes into the binary decoders, which should never panic,
+//! and checks that whatever they decode encodes back to the same bytes.
+//! Run with `cargo fuzz run decode_bytes` from the repository root.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ramino::{card::Card, hand::Hand, run::Run};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(card) = Card::from_bytes(data) {
+        assert_eq!(card.to_bytes(), data);
+    }
+    if let Some(hand) = Hand::from_bytes(data) {
+        assert_eq!(hand.to_bytes(), data);
+    }
+    if let Some(run) = Run::from_bytes(data) {
+        assert_eq!(run.to_bytes(), data);
+    }
+});
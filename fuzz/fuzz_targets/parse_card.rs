@@ -0,0 +1,13 @@
+//! Feeds arbitrary bytes into the card parser, which should never panic.
+//! Run with `cargo fuzz run parse_card` from the repository root.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ramino::card::Card;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(notation) = std::str::from_utf8(data) {
+        let _ = Card::try_from(notation);
+    }
+});
@@ -1,13 +1,19 @@
 //! The card module contains all stuff related to cards.
-//! This entails: the CardType, Suit and CardOrdering enums and the Card struct.
+//! This entails: the CardType, Suit, CardOrdering and CardParseError enums and the Card struct.
 
 use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::DEFAULT_JOKER_SCORE;
+use crate::rules::ScoringRules;
 
 /// An enum to represent the type of a card.
 ///
 /// The Number(u8) variant can only hold values between 2 and 10, since the ace
 /// is accounted for separately.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[allow(missing_docs)]
 pub enum CardType {
     Number(u8), //TODO: make invalid state unrepresentable
@@ -22,7 +28,8 @@ pub enum CardType {
 ///
 /// An exception is made for jokers, since they don't strictly have a suit, but
 /// making the suit field on a struct an Option would be hell.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[allow(missing_docs)]
 pub enum Suit {
     Spades,
@@ -33,7 +40,8 @@ pub enum Suit {
 }
 
 /// The main Card struct.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[allow(missing_docs)]
 pub struct Card {
     pub card_type: CardType,
@@ -42,20 +50,21 @@ pub struct Card {
 
 impl PartialOrd for Card {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.suit == other.suit {
-            match self.compare(other) {
-                CardOrdering::WellDefined(o) => return Some(o),
-                CardOrdering::IllDefined     => return None
-            }
-        } else {
-            return Some(self.suit.cmp(&other.suit));
-        }
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for Card {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap_or(Ordering::Less)
+        if self.suit == other.suit {
+            match self.compare(other) {
+                CardOrdering::WellDefined(o) => o,
+                // this only happens for jokers, which go after all other cards
+                CardOrdering::IllDefined     => self.get_comparison_value().cmp(&other.get_comparison_value())
+            }
+        } else {
+            self.suit.cmp(&other.suit)
+        }
     }
 }
 
@@ -75,18 +84,57 @@ pub enum CardOrdering {
     IllDefined
 }
 
-impl From<&str> for Card {
-    fn from(val: &str) -> Self {
-        assert!(val.len() <= 2, "cannot construct card from string with length bigger than 2");
+/// An enum to represent everything that can go wrong when parsing a card from
+/// its notation (e.g. "S2" for the two of spades, or "J" for a joker).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardParseError {
+    /// The string to parse was empty.
+    EmptyString,
+    /// The string to parse held more than two characters.
+    TooLong,
+    /// The contained character doesn't stand for a suit.
+    InvalidSuit(char),
+    /// The contained character doesn't stand for a card type.
+    InvalidType(char)
+}
+
+impl fmt::Display for CardParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CardParseError::EmptyString    => write!(f, "cannot construct card from an empty string"),
+            CardParseError::TooLong        => write!(f, "cannot construct card from string with length bigger than 2"),
+            CardParseError::InvalidSuit(c) => write!(f, "encountered invalid character {:?} in getting suit for card", c),
+            CardParseError::InvalidType(c) => write!(f, "encountered invalid character {:?} in getting type for card", c)
+        }
+    }
+}
+
+impl std::error::Error for CardParseError {}
+
+impl TryFrom<char> for Suit {
+    type Error = CardParseError;
+
+    /// Accepts both the letters used in card notation and the unicode suit
+    /// symbols (in both their black and white variants). The joker glyph 🃏
+    /// stands for the joker suit, just like the letter J.
+    fn try_from(val: char) -> Result<Self, Self::Error> {
+        match val {
+            'H' | '♥' | '♡' => Ok(Suit::Hearts),
+            'C' | '♣' | '♧' => Ok(Suit::Clubs),
+            'D' | '♦' | '♢' => Ok(Suit::Diamonds),
+            'S' | '♠' | '♤' => Ok(Suit::Spades),
+            'J' | '🃏'       => Ok(Suit::JokerSuit),
+            _               => Err(CardParseError::InvalidSuit(val))
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = CardParseError;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
         let mut chars = val.chars();
-        let suit = match chars.next().unwrap() {
-            'H' => Suit::Hearts,
-            'C' => Suit::Clubs,
-            'D' => Suit::Diamonds,
-            'S' => Suit::Spades,
-            'J' => Suit::JokerSuit,
-            _   => panic!("encountered invalid character in getting suit for card")
-        };
+        let suit = Suit::try_from(chars.next().ok_or(CardParseError::EmptyString)?)?;
         let card_type = match chars.next().unwrap_or('?') {
             'A' => CardType::Ace,
             'J' => CardType::Jack,
@@ -102,10 +150,21 @@ impl From<&str> for Card {
             '9' => CardType::Number(9),
             'X' => CardType::Number(10),
             '?' => CardType::Joker,
-            _ => panic!("encountered invalid character in getting type for card")
+            c   => return Err(CardParseError::InvalidType(c))
         };
+        if chars.next().is_some() { return Err(CardParseError::TooLong); }
+
+        Ok(Card::new(card_type, suit))
+    }
+}
+
+/// Constructs a card from its notation, just like `str::parse` does. This
+/// never panics: invalid notation is reported as a CardParseError.
+impl TryFrom<&str> for Card {
+    type Error = CardParseError;
 
-        Card::new(card_type, suit)
+    fn try_from(val: &str) -> Result<Self, Self::Error> {
+        val.parse()
     }
 }
 
@@ -115,6 +174,16 @@ impl Card {
         Card { card_type, suit }
     }
 
+    /// Returns the suit of this card.
+    pub fn suit(&self) -> Suit {
+        self.suit
+    }
+
+    /// Returns the type (rank) of this card.
+    pub fn rank(&self) -> CardType {
+        self.card_type
+    }
+
     /// Calculates the score for a single card. An ace is counted as 11 by
     /// default, since the "an ace counts as one if it's the only card left"
     /// rule is accounted for in the synonymous function on Hand.
@@ -125,11 +194,152 @@ impl Card {
         match self.card_type {
             CardType::Number(n) => n,
             CardType::Ace => 11, // the singular ace amounting to one is accounted for in Hand
-            CardType::Joker => 25,
+            CardType::Joker => DEFAULT_JOKER_SCORE,
             _ => 10
         }
     }
 
+    /// Calculates the score for a single card according to the given rules.
+    /// This is the same as score(), except for jokers counting as `rules.joker_value`.
+    pub fn score_with(&self, rules: &ScoringRules) -> u8 {
+        match self.card_type {
+            CardType::Joker => rules.joker_value,
+            _ => self.score()
+        }
+    }
+
+    /// Gets the numeric id of this card, which uniquely identifies it within a
+    /// single deck (save for the jokers, which are all the same).
+    ///
+    /// The ids 0 through 51 belong to the normal cards, ordered by suit (spades,
+    /// diamonds, clubs, hearts) and then by rank (ace, two through ten, jack,
+    /// queen, king). Jokers have id 52.
+    pub fn to_id(&self) -> u8 {
+        let rank = match self.card_type {
+            CardType::Ace       => 0,
+            CardType::Number(n) => n - 1,
+            CardType::Jack      => 10,
+            CardType::Queen     => 11,
+            CardType::King      => 12,
+            CardType::Joker     => return 52
+        };
+
+        let suit = match self.suit {
+            Suit::Spades    => 0,
+            Suit::Diamonds  => 1,
+            Suit::Clubs     => 2,
+            Suit::Hearts    => 3,
+            Suit::JokerSuit => return 52
+        };
+
+        suit * 13 + rank
+    }
+
+    /// Gets the card belonging to the given id (see to_id()), or None if there
+    /// is no such card, i.e. for any id above 52. This never panics, so it's
+    /// safe to call on untrusted bytes.
+    pub fn from_id(id: u8) -> Option<Self> {
+        if id == 52 { return Some(Card::new(CardType::Joker, Suit::JokerSuit)); }
+
+        let suit = match id / 13 {
+            0 => Suit::Spades,
+            1 => Suit::Diamonds,
+            2 => Suit::Clubs,
+            3 => Suit::Hearts,
+            _ => return None
+        };
+
+        let card_type = match id % 13 {
+            0  => CardType::Ace,
+            10 => CardType::Jack,
+            11 => CardType::Queen,
+            12 => CardType::King,
+            n  => CardType::Number(n + 1)
+        };
+
+        Some(Card::new(card_type, suit))
+    }
+
+    /// Renders this card in its canonical notation, the one it's parsed from:
+    /// the suit letter followed by the rank character, e.g. "SA" or "HX".
+    /// Jokers are rendered as "J".
+    pub fn to_notation(&self) -> String {
+        let suit = match self.suit {
+            Suit::Spades    => 'S',
+            Suit::Diamonds  => 'D',
+            Suit::Clubs     => 'C',
+            Suit::Hearts    => 'H',
+            Suit::JokerSuit => return "J".to_string()
+        };
+
+        format!("{}{}", suit, self.rank_char())
+    }
+
+    /// Renders this card with a unicode suit symbol followed by the same rank
+    /// character as in card notation, e.g. "♠A" or "♥X". Jokers are rendered
+    /// as "🃏". The result can be parsed back into the same card.
+    pub fn to_unicode(&self) -> String {
+        let suit = match self.suit {
+            Suit::Spades    => '♠',
+            Suit::Diamonds  => '♦',
+            Suit::Clubs     => '♣',
+            Suit::Hearts    => '♥',
+            Suit::JokerSuit => return "🃏".to_string()
+        };
+
+        format!("{}{}", suit, self.rank_char())
+    }
+
+    /// Returns the character that stands for the rank of this card in card notation.
+    fn rank_char(&self) -> char {
+        match self.card_type {
+            CardType::Ace        => 'A',
+            CardType::Jack       => 'J',
+            CardType::Queen      => 'Q',
+            CardType::King       => 'K',
+            CardType::Number(10) => 'X',
+            CardType::Number(n)  => char::from(b'0' + n),
+            CardType::Joker      => '?'
+        }
+    }
+
+    /// Returns a human-readable identifier for this card that's safe to use in
+    /// a filename, e.g. to look up its artwork: the suit and the rank in lower
+    /// case, separated by an underscore ("spades_ace", "hearts_10"), or just
+    /// "joker" for a joker. Different cards never share a key.
+    pub fn asset_key(&self) -> String {
+        let suit = match self.suit {
+            Suit::Spades    => "spades",
+            Suit::Diamonds  => "diamonds",
+            Suit::Clubs     => "clubs",
+            Suit::Hearts    => "hearts",
+            Suit::JokerSuit => return "joker".to_string()
+        };
+
+        match self.card_type {
+            CardType::Ace       => format!("{}_ace", suit),
+            CardType::Jack      => format!("{}_jack", suit),
+            CardType::Queen     => format!("{}_queen", suit),
+            CardType::King      => format!("{}_king", suit),
+            CardType::Number(n) => format!("{}_{}", suit, n),
+            CardType::Joker     => "joker".to_string()
+        }
+    }
+
+    /// Encodes this card as a single byte, its id (see to_id()).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        vec![self.to_id()]
+    }
+
+    /// Decodes a card from the format written by to_bytes(). Returns None if
+    /// the bytes don't hold exactly one valid card id.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [id] => Card::from_id(*id),
+            _    => None
+        }
+    }
+
     /// Gets the raw comparison value to test against.
     pub fn get_comparison_value(&self) -> u8 {
         match self.card_type {
@@ -145,16 +355,21 @@ impl Card {
     /// Gets the distance between two cards, accounting for the fact that
     /// aces and kings are also adjacent.
     ///
+    /// Returns None if either card is a joker, since a joker doesn't have a
+    /// position of its own.
+    ///
     /// ## Notes
     /// This function is always used in a sorted iterator, with self being the smaller.
-    pub fn get_distance(&self, other: &Self) -> u8 {
+    pub fn get_distance(&self, other: &Self) -> Option<u8> {
+        if self.card_type == CardType::Joker || other.card_type == CardType::Joker { return None; }
+
         let self_val = self.get_comparison_value();
         let other_val = other.get_comparison_value();
 
         // this is in practice always used in sorted runs,
         // so other_val will always be a greater card.
-        if self_val == 1 && other_val == 13 { 1 }
-        else { other_val - self_val }
+        if self_val == 1 && other_val == 13 { Some(1) }
+        else { Some(other_val - self_val) }
     }
 
     /// Compares two cards and returns a CardOrdering.
@@ -176,7 +391,7 @@ impl Card {
             return CardOrdering::WellDefined(self_val.cmp(&other_val));
         }
 
-        return CardOrdering::IllDefined; // cannot *really* compare two cards of different suits
+        CardOrdering::IllDefined // cannot *really* compare two cards of different suits
 
     }
 }
@@ -0,0 +1,1067 @@
+//! The game module contains the Player and Game structs, which together drive
+//! an actual game of Ramino.
+
+use std::fmt;
+
+use crate::card::{Card, CardType};
+use crate::hand::Hand;
+use crate::run::{Run, RunError};
+use crate::rules::{GameConfig, InitialJoker, OpeningError};
+use crate::strategy::{PlayerView, Strategy};
+use crate::table::{DrawSource, Table};
+use crate::utils::{count_mismatches, generate_seeded_deck, min_deck_for};
+use crate::HAND_SIZE;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// A struct that represents a single player in a game.
+///
+/// The melds field holds the indices of the melds on the table that this
+/// player laid down, and the dealt field the hand they were dealt at the start
+/// of the round.
+#[derive(Debug, Clone)]
+#[allow(missing_docs)]
+pub struct Player {
+    pub hand: Hand,
+    pub melds: Vec<usize>,
+    pub score: u16,
+    pub dealt: Vec<Card>
+}
+
+impl Player {
+    /// Returns every card this player holds or has melded: their hand,
+    /// followed by the cards of the melds they laid down on the given table
+    /// (including any cards laid off onto those melds since).
+    pub fn accounted_cards(&self, table: &Table) -> Vec<Card> {
+        let mut cards = self.hand.0.clone();
+        cards.extend(self.melds.iter().flat_map(|&i| table.melds[i].cards().iter().copied()));
+
+        cards
+    }
+}
+
+/// What a spectator gets to see of a single player: their melds and score,
+/// but only the size of their hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub struct PlayerSnapshot {
+    pub melds: Vec<Run>,
+    pub hand_size: usize,
+    pub score: u16
+}
+
+/// A read-only view of a game as a spectator gets to see it, which never
+/// includes the contents of any hand or of the stock (see Game::snapshot()).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub struct GameSnapshot {
+    pub players: Vec<PlayerSnapshot>,
+    pub discard_top: Option<Card>,
+    pub stock_size: usize,
+    pub current_player: usize
+}
+
+/// An enum to represent everything that can happen during a game, as it's
+/// recorded in the event log of a Game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// A player drew a card from the stock or the discard pile.
+    Draw {
+        /// The index of the player who drew.
+        player: usize,
+        /// Where the card was drawn from.
+        source: DrawSource,
+        /// The card that was drawn.
+        card: Card
+    },
+    /// A player laid down melds onto the table.
+    Meld {
+        /// The index of the player who melded.
+        player: usize,
+        /// The melds that were laid down.
+        runs: Vec<Run>
+    },
+    /// A player laid off a card onto a meld on the table.
+    LayOff {
+        /// The index of the player who laid off.
+        player: usize,
+        /// The index of the meld in the table's melds.
+        meld: usize,
+        /// The card that was laid off.
+        card: Card
+    },
+    /// A player discarded a card onto the discard pile.
+    Discard {
+        /// The index of the player who discarded.
+        player: usize,
+        /// The card that was discarded.
+        card: Card
+    },
+    /// A player took a meld they laid down during the same turn back into
+    /// their hand (see Game::undo_meld()).
+    UndoMeld {
+        /// The index of the player who took the meld back.
+        player: usize,
+        /// The index the meld had in the table's melds.
+        meld: usize,
+        /// The meld that was taken back, including any cards laid off onto it.
+        run: Run
+    }
+}
+
+/// An enum to represent how far along the current player is in their turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnPhase {
+    /// The player still has to draw a card.
+    Draw,
+    /// The player has drawn, and may now meld and lay off cards before ending
+    /// their turn by discarding.
+    Play
+}
+
+/// An enum to represent everything a player can do during their turn (see
+/// Game::legal_moves()).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Move {
+    /// Draw the top card of the stock.
+    DrawStock,
+    /// Draw the top card of the discard pile.
+    DrawDiscard,
+    /// Lay down the given melds from the hand onto the table.
+    Meld(Vec<Run>),
+    /// Add a card from the hand to the meld on the table at the given index.
+    LayOff {
+        /// The index of the meld in the table's melds.
+        meld: usize,
+        /// The card to lay off.
+        card: Card
+    },
+    /// Discard the given card from the hand, which ends the turn.
+    Discard(Card)
+}
+
+/// A struct that bundles everything a player intends to do during a whole
+/// turn (see Game::validate_turn()): where they draw from, the melds and
+/// layoffs they make (as Move::Meld and Move::LayOff, in order), and the card
+/// they discard, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub struct TurnPlan {
+    pub draw: DrawSource,
+    pub plays: Vec<Move>,
+    pub discard: Option<Card>
+}
+
+/// The main Game struct, holding the players and the table they play at.
+///
+/// The seed is the one the deck was shuffled with (see new_game()), or None if
+/// the game was dealt from a given deck. The events field holds everything
+/// that happened in the game so far, in order.
+///
+/// The rng field is there for anything that needs randomness while the game is
+/// played, e.g. a bot simulating ahead. It's seeded by the seed (or by zero if
+/// there is none), so it's reproducible. Cloning a game clones the state of its
+/// rng along with it, so a clone continues the very same stream as the
+/// original: both produce the same numbers from then on. To get a clone with
+/// a stream of its own, use fork() instead.
+///
+/// The uncommitted_melds field holds the indices of the melds on the table
+/// that the current player laid down during their current turn, which they
+/// can still take back until they discard (see undo_meld()).
+///
+/// The deck field holds every card the game was dealt from, in canonical
+/// order, so the table can be checked against it (see Table::validate_against()).
+#[derive(Debug, Clone)]
+#[allow(missing_docs)]
+pub struct Game {
+    pub players: Vec<Player>,
+    pub table: Table,
+    pub current_player: usize,
+    pub phase: TurnPhase,
+    pub dealer: usize,
+    pub config: GameConfig,
+    pub seed: Option<u64>,
+    pub events: Vec<GameEvent>,
+    pub rng: StdRng,
+    pub uncommitted_melds: Vec<usize>,
+    pub deck: Vec<Card>
+}
+
+/// An enum to represent the reasons why a game can't be set up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupError {
+    /// A game needs at least one player.
+    NoPlayers,
+    /// The deck holds too few cards to deal to every player and leave a stock
+    /// (see min_deck_for()).
+    DeckTooSmall {
+        /// The minimum amount of cards the deck should have held.
+        needed: usize,
+        /// The amount of cards the deck actually held.
+        available: usize
+    }
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::NoPlayers => write!(f, "a game needs at least one player"),
+            SetupError::DeckTooSmall { needed, available } =>
+                write!(f, "the deck needs at least {} cards for this many players, but only holds {}", needed, available)
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+/// An enum to represent the reasons why a move can't be made (see Game::apply()).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    /// The move can't be made in the current phase of the turn, e.g. discarding
+    /// before drawing or drawing twice.
+    WrongPhase,
+    /// There is nothing to draw from the chosen pile.
+    EmptyPile,
+    /// The discard pile is frozen (see Table::frozen), so it can't be drawn from.
+    FrozenPile,
+    /// The contained card isn't in the current player's hand (or not as many
+    /// times as the move uses it).
+    NotInHand(Card),
+    /// One of the melds isn't a valid run of its kind.
+    InvalidMeld(RunError),
+    /// The player hasn't opened yet and can't open with these melds.
+    Opening(OpeningError),
+    /// The card can't be laid off: the player hasn't opened yet, there is no
+    /// meld at the given index, or the card doesn't fit onto it.
+    CannotLayOff,
+    /// The move would leave the player without a card to discard.
+    MustKeepCard,
+    /// The meld wasn't laid down during the current turn, or the player laid
+    /// off cards that depend on it, so it can't be taken back (see Game::undo_meld()).
+    CannotUndo,
+    /// A player has gone out, so the round is over and no more moves can be
+    /// made in it (see Game::round_winner()).
+    RoundOver
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::WrongPhase     => write!(f, "this move can't be made in the current phase of the turn"),
+            MoveError::EmptyPile      => write!(f, "there is nothing to draw from this pile"),
+            MoveError::FrozenPile     => write!(f, "the discard pile is frozen"),
+            MoveError::NotInHand(c)   => write!(f, "{:?} isn't in the player's hand", c),
+            MoveError::InvalidMeld(e) => write!(f, "invalid meld: {}", e),
+            MoveError::Opening(e)     => write!(f, "can't open: {}", e),
+            MoveError::CannotLayOff   => write!(f, "this card can't be laid off onto this meld"),
+            MoveError::MustKeepCard   => write!(f, "the player has to keep a card to discard"),
+            MoveError::CannotUndo     => write!(f, "this meld can't be taken back anymore"),
+            MoveError::RoundOver      => write!(f, "a player has gone out, so the round is over")
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// Sets up a complete game for the given amount of players: a deck is
+/// generated according to the config and shuffled by the seed, after which
+/// every player is dealt a hand, one card is turned face-up onto the discard
+/// pile and the rest of the deck becomes the stock.
+pub fn new_game(players: usize, rules: &GameConfig, seed: u64) -> Result<Game, SetupError> {
+    let mut game = Game::from_deck(generate_seeded_deck(seed, rules), players, rules)?;
+    game.seed = Some(seed);
+    game.rng = StdRng::seed_from_u64(seed);
+
+    Ok(game)
+}
+
+impl Game {
+    /// Sets up a game by dealing a hand to every player from the given deck,
+    /// turning the next card face-up onto the discard pile and keeping the
+    /// rest of the deck as the stock. The game is played by the default GameConfig.
+    ///
+    /// The first player deals, so the player to their left gets to start.
+    /// Returns a SetupError if there are no players or if the deck is too small.
+    pub fn deal(deck: Vec<Card>, players: usize) -> Result<Self, SetupError> {
+        Game::from_deck(deck, players, &GameConfig::default())
+    }
+
+    /// Sets up a game exactly like deal() does, but played by the given config,
+    /// e.g. to script a scenario with known hands.
+    ///
+    /// The deck is dealt from its end: the first player gets the last thirteen
+    /// cards (the very last one first), the second player the thirteen before
+    /// those, and so on. The card before those is turned face-up onto the
+    /// discard pile, and the rest of the deck is kept as the stock, in order.
+    /// If that card is a joker, `rules.initial_joker` decides what happens to it.
+    pub fn from_deck(mut deck: Vec<Card>, players: usize, rules: &GameConfig) -> Result<Self, SetupError> {
+        if players == 0 { return Err(SetupError::NoPlayers); }
+
+        let mut original = deck.clone();
+        original.sort();
+        let needed = min_deck_for(players, HAND_SIZE.into());
+        if deck.len() < needed {
+            return Err(SetupError::DeckTooSmall { needed, available: deck.len() });
+        }
+
+        let current_player = 1 % players;
+        let players = (0..players).map(|_| {
+            let hand = Hand::draw(&mut deck);
+            Player { dealt: hand.0.clone(), hand, melds: Vec::new(), score: 0 }
+        }).collect();
+        let mut flipped = deck.pop().unwrap();
+        if rules.initial_joker == InitialJoker::Bury {
+            // every card is tried at most once, in case the stock holds nothing but jokers
+            for _ in 0..deck.len() {
+                if flipped.card_type != CardType::Joker { break; }
+                deck.insert(0, flipped);
+                flipped = deck.pop().unwrap();
+            }
+        }
+
+        let mut table = Table { stock: deck, freeze_on_joker: rules.jokers_freeze_discard, ..Table::default() };
+        table.discard(flipped);
+
+        Ok(Game {
+            players,
+            table,
+            current_player,
+            phase: TurnPhase::Draw,
+            dealer: 0,
+            config: *rules,
+            seed: None,
+            events: Vec::new(),
+            rng: StdRng::seed_from_u64(0),
+            uncommitted_melds: Vec::new(),
+            deck: original
+        })
+    }
+
+    /// Passes the deal on to the next player, for the next round. The player to
+    /// the left of the new dealer becomes the current player.
+    pub fn rotate_dealer(&mut self) {
+        self.dealer = (self.dealer + 1) % self.players.len();
+        self.current_player = (self.dealer + 1) % self.players.len();
+    }
+
+    /// Returns the index of the player who went out, i.e. the one who has no
+    /// cards left, or None if nobody has (yet), e.g. when the round ended in a
+    /// stalemate (see is_stalemate()).
+    pub fn round_winner(&self) -> Option<usize> {
+        self.players.iter().position(|p| p.hand.0.is_empty())
+    }
+
+    /// Returns every player's index along with their total score across the
+    /// rounds so far (see score_round()), best first. Since scores are penalty
+    /// points, the lowest score is the best; players with equal scores keep
+    /// their seating order.
+    pub fn standings(&self) -> Vec<(usize, u16)> {
+        let mut standings: Vec<(usize, u16)> = self.players.iter().map(|p| p.score).enumerate().collect();
+        standings.sort_by_key(|&(i, score)| (score, i));
+
+        standings
+    }
+
+    /// Returns the overall winner of the match if it's over, i.e. if any player
+    /// has reached or exceeded the target score. Since scores are penalty
+    /// points, that player is out, and the player with the lowest score wins
+    /// (the first of them in seating order, if several are tied; see standings()).
+    pub fn match_over(&self, target: u16) -> Option<usize> {
+        if self.players.iter().all(|p| p.score < target) { return None; }
+
+        self.standings().first().map(|&(i, _)| i)
+    }
+
+    /// Returns true if the given player went out with a perfect close: they
+    /// melded their entire hand in the very turn they went out, without having
+    /// laid anything down before (whether or not they discarded at the end).
+    /// This is read from the event log.
+    pub fn is_perfect_close(&self, player: usize) -> bool {
+        if !self.players[player].hand.0.is_empty() { return false; }
+
+        let events: Vec<&GameEvent> = self.events.iter().filter(|e| match e {
+            GameEvent::Draw { player: p, .. } | GameEvent::Meld { player: p, .. } | GameEvent::LayOff { player: p, .. }
+            | GameEvent::Discard { player: p, .. } | GameEvent::UndoMeld { player: p, .. } => *p == player
+        }).collect();
+
+        match events.iter().rposition(|e| matches!(e, GameEvent::Draw { .. })) {
+            Some(i) => !events[..i].iter().any(|e| matches!(e, GameEvent::Meld { .. } | GameEvent::LayOff { .. })),
+            None => false
+        }
+    }
+
+    /// Scores a round that has ended according to `config.scoring`: the player
+    /// who went out (the one without any cards left) gets the winner bonus
+    /// taken off their total, along with the perfect close bonus if they went
+    /// out with a perfect close (see is_perfect_close()), while every other
+    /// player gets the score of their remaining hand (see Hand::score_with())
+    /// added to theirs.
+    pub fn score_round(&mut self) {
+        let rules = self.config.scoring;
+        let perfect: Vec<bool> = (0..self.players.len()).map(|i| self.is_perfect_close(i)).collect();
+
+        for (player, perfect) in self.players.iter_mut().zip(perfect) {
+            if player.hand.0.is_empty() {
+                let bonus = if perfect { rules.winner_bonus.saturating_add(rules.perfect_close_bonus) } else { rules.winner_bonus };
+                player.score = player.score.saturating_sub(bonus);
+            } else {
+                player.score += player.hand.score_with(&rules);
+            }
+        }
+    }
+
+    /// Takes a snapshot of the game for spectators, holding everything that's
+    /// public knowledge: the melds, hand sizes and scores of every player, the
+    /// top of the discard pile, the size of the stock and whose turn it is.
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            players: self.players.iter().map(|p| PlayerSnapshot {
+                melds: p.melds.iter().map(|&i| self.table.melds[i].clone()).collect(),
+                hand_size: p.hand.0.len(),
+                score: p.score
+            }).collect(),
+            discard_top: self.table.peek_discard().copied(),
+            stock_size: self.table.stock.len(),
+            current_player: self.current_player
+        }
+    }
+
+    /// Estimates how likely it is that the given opponent benefits from the
+    /// given card being discarded, as a number between 0 and 1. Only what the
+    /// current player legally knows is taken into account.
+    ///
+    /// The heuristic goes as follows:
+    /// * a joker, or a card that can be laid off onto one of the opponent's
+    ///   melds, is a sure hit (1.0);
+    /// * a card of the same rank as, or of the same suit and at most two ranks
+    ///   away from, a card in the opponent's melds is likely to help (0.5);
+    /// * any other card might still help the opponent's hidden hand, which is
+    ///   more likely the more cards they hold (at most 0.1, for a full hand).
+    pub fn discard_danger(&self, card: &Card, opponent: usize) -> f64 {
+        let melds: Vec<&Run> = self.players[opponent].melds.iter().map(|&i| &self.table.melds[i]).collect();
+
+        if card.card_type == CardType::Joker || melds.iter().any(|m| m.can_append(card)) {
+            return 1.0;
+        }
+
+        let near = melds.iter().flat_map(|m| m.cards()).any(|c| {
+            c.card_type == card.card_type ||
+            (c.suit == card.suit && c.get_comparison_value().abs_diff(card.get_comparison_value()) <= 2)
+        });
+        if near { return 0.5; }
+
+        let hand_size = self.players[opponent].hand.0.len().min(HAND_SIZE.into());
+        0.1 * hand_size as f64 / f64::from(HAND_SIZE)
+    }
+
+    /// Finds the cheapest card for the current player to give away, i.e. the
+    /// lowest one that's unlikely to help an opponent, or returns None if their
+    /// hand is empty. Only deadwood (see Hand::best_partition_with()) is
+    /// considered, unless the hand doesn't hold any.
+    ///
+    /// The cost of a card is its score (see Card::score_with()) plus its danger
+    /// (the highest discard_danger() over all opponents) times the value of a
+    /// joker: handing a sure hit to an opponent is taken to be as bad as giving
+    /// them a joker. The card with the lowest cost wins, and between equally
+    /// costly cards, the lowest one in the standard order. So among safe cards,
+    /// this keeps the high ones, which count the most towards melds (and an
+    /// opening), even though they're also the ones that cost the most points
+    /// when they're still in hand at the end of the round.
+    pub fn optimal_defensive_discard(&self) -> Option<Card> {
+        let rules = &self.config.scoring;
+        let hand = &self.players[self.current_player].hand;
+        let deadwood = hand.best_partition_with(rules).1;
+        let candidates = if deadwood.is_empty() { &hand.0 } else { &deadwood };
+
+        let cost = |card: &Card| {
+            let danger = (0..self.players.len())
+                .filter(|&i| i != self.current_player)
+                .map(|i| self.discard_danger(card, i))
+                .fold(0.0, f64::max);
+            f64::from(card.score_with(rules)) + danger * f64::from(rules.joker_value)
+        };
+
+        candidates.iter().copied().min_by(|a, b| cost(a).total_cmp(&cost(b)).then(a.cmp(b)))
+    }
+
+    /// Checks that the cards the given player accounts for (see
+    /// Player::accounted_cards()) are exactly the ones the event log says they
+    /// should have: the cards they were dealt and drew, minus the ones they
+    /// discarded and laid off onto someone else's meld, plus the ones other
+    /// players laid off onto their melds.
+    ///
+    /// If that isn't the case, returns every card whose count is off, along
+    /// with how many copies are missing (a negative count means there are
+    /// copies too many), just like validate_deck().
+    pub fn verify_player(&self, player: usize) -> Result<(), Vec<(Card, isize)>> {
+        let mut expected = self.players[player].dealt.clone();
+        let mut gone = Vec::new();
+
+        // which melds are the player's changes along the way, so it's replayed too
+        let (mut own, mut melds) = (Vec::new(), 0);
+        for event in self.events.iter() {
+            match *event {
+                GameEvent::Draw { player: p, card, .. } if p == player => expected.push(card),
+                GameEvent::Discard { player: p, card } if p == player => gone.push(card),
+                GameEvent::Meld { player: p, ref runs } => {
+                    if p == player { own.extend(melds..melds + runs.len()); }
+                    melds += runs.len();
+                },
+                GameEvent::LayOff { player: p, meld, card } => match (p == player, own.contains(&meld)) {
+                    (true, false) => gone.push(card),
+                    (false, true) => expected.push(card),
+                    _ => {}
+                },
+                GameEvent::UndoMeld { meld, .. } => {
+                    own.retain(|&i| i != meld);
+                    for i in own.iter_mut() { if *i > meld { *i -= 1; } }
+                    melds -= 1;
+                },
+                _ => {}
+            }
+        }
+
+        let accounted = self.players[player].accounted_cards(&self.table);
+        let mismatched = count_mismatches(&expected, &[&accounted, &gone]);
+        if mismatched.is_empty() { Ok(()) } else { Err(mismatched) }
+    }
+
+    /// Clones this game, but gives the clone an rng stream of its own, e.g. for
+    /// every branch of a search tree. The new stream is seeded by the next
+    /// number the original's stream would produce, mixed with the given branch,
+    /// so forking the same game with the same branch always gives the same
+    /// stream, and the original's stream isn't advanced.
+    pub fn fork(&self, branch: u64) -> Game {
+        let mut game = self.clone();
+        let next: u64 = game.rng.gen();
+        game.rng = StdRng::seed_from_u64(next ^ branch);
+
+        game
+    }
+
+    /// Calculates a fingerprint of the game so far out of its seed and its
+    /// event log, in order. Replaying the same events from the same seed always
+    /// leads to the same fingerprint, so it can be used to verify results.
+    ///
+    /// ## Notes
+    /// This uses 64-bit FNV-1a over a fixed binary encoding of the seed and the
+    /// events (see Card::to_bytes() and Run::to_bytes()), so the fingerprint
+    /// doesn't depend on the platform or the version of Rust.
+    pub fn fingerprint(&self) -> u64 {
+        let mut bytes = match self.seed {
+            Some(seed) => [&[1][..], &seed.to_le_bytes()].concat(),
+            None       => vec![0]
+        };
+
+        for event in self.events.iter() {
+            match event {
+                GameEvent::Draw { player, source, card } => {
+                    bytes.extend([0, *player as u8, *source as u8]);
+                    bytes.extend(card.to_bytes());
+                },
+                GameEvent::Meld { player, runs } => {
+                    bytes.extend([1, *player as u8, runs.len() as u8]);
+                    for run in runs { bytes.extend(run.to_bytes()); }
+                },
+                GameEvent::Discard { player, card } => {
+                    bytes.extend([2, *player as u8]);
+                    bytes.extend(card.to_bytes());
+                },
+                GameEvent::LayOff { player, meld, card } => {
+                    bytes.extend([3, *player as u8, *meld as u8]);
+                    bytes.extend(card.to_bytes());
+                },
+                GameEvent::UndoMeld { player, meld, run } => {
+                    bytes.extend([4, *player as u8, *meld as u8]);
+                    bytes.extend(run.to_bytes());
+                }
+            }
+        }
+
+        bytes.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ u64::from(b)).wrapping_mul(0x100000001b3))
+    }
+
+    /// Finds the first opponent, in turn order, who could go out right away if
+    /// they got the given card, e.g. by picking it up from the discard pile.
+    /// Returns None if there is no such opponent.
+    ///
+    /// ## Notes
+    /// Like discard_danger(), this only takes what the current player legally
+    /// knows into account: the melds on the table and the size of every hand.
+    /// An opponent is taken to go out with the card if they've opened, the
+    /// card can be laid off onto a meld on the table (see Table::layoff_options())
+    /// and they hold a single card, which is left to discard after that. Ways
+    /// of going out that depend on their hidden cards, like melding the card
+    /// along with them, can't be seen, so they aren't taken into account.
+    /// Nobody can pick the card up from a frozen discard pile (see Table::frozen),
+    /// or from one that the card itself freezes, being a joker.
+    pub fn enables_opponent_out(&self, card: &Card) -> Option<usize> {
+        if self.table.frozen || (self.table.freeze_on_joker && card.card_type == CardType::Joker) { return None; }
+        if self.table.layoff_options(card).is_empty() { return None; }
+
+        (1..self.players.len())
+            .map(|offset| (self.current_player + offset) % self.players.len())
+            .find(|&opponent| {
+                let player = &self.players[opponent];
+                !player.melds.is_empty() && player.hand.0.len() == 1
+            })
+    }
+
+    /// Returns true if every copy of the given card in the deck is accounted
+    /// for by what the current player can see: the melds on the table, the
+    /// discard pile (every card of which was seen when it was discarded) and
+    /// their own hand. Nobody can draw such a card anymore, save for when the
+    /// discard pile gets turned over to replenish the stock.
+    ///
+    /// The copies are counted in the deck the game was dealt from (see the
+    /// deck field).
+    pub fn is_card_dead(&self, card: &Card) -> bool {
+        let copies = self.deck.iter().filter(|&c| c == card).count();
+
+        let visible = self.table.melds.iter().flat_map(|m| m.cards())
+            .chain(self.table.discard.iter())
+            .chain(self.players[self.current_player].hand.0.iter())
+            .filter(|&c| c == card)
+            .count();
+
+        visible >= copies
+    }
+
+    /// Counts the ways the cards the given player hasn't seen could be spread
+    /// over the stock and the hands of their opponents, i.e. how many deals are
+    /// consistent with what they've observed. Saturates at u128::MAX.
+    ///
+    /// The cards an opponent took from the discard pile and hasn't played since
+    /// (according to the event log) are known to be in their hand, which leaves
+    /// h_i unknown cards in the hand of opponent i and the s cards of the stock.
+    /// Telling every physical card apart (so the two copies of a card count as
+    /// different cards), the n = s + Σ h_i unseen cards can be spread over them
+    /// in the multinomial n! / (s! · Π h_i!) ways, i.e. C(n, h_1) · C(n - h_1, h_2) · …
+    ///
+    /// ## Notes
+    /// Since the copies of a card are interchangeable in play, this is an upper
+    /// bound on the amount of deals that actually differ. Cards that went into
+    /// the stock when it was replenished from the discard pile are counted as
+    /// unseen too.
+    pub fn information_set_size(&self, player: usize) -> u128 {
+        let mut known: Vec<Vec<Card>> = vec![Vec::new(); self.players.len()];
+        let forget = |known: &mut Vec<Card>, card: &Card| {
+            if let Some(index) = known.iter().position(|c| c == card) { known.remove(index); }
+        };
+        for event in self.events.iter() {
+            match event {
+                GameEvent::Draw { player, source: DrawSource::Discard, card } => known[*player].push(*card),
+                GameEvent::Draw { .. } => {},
+                GameEvent::Meld { player, runs } => for card in runs.iter().flat_map(|r| r.cards()) { forget(&mut known[*player], card) },
+                GameEvent::LayOff { player, card, .. } | GameEvent::Discard { player, card } => forget(&mut known[*player], card),
+                GameEvent::UndoMeld { player, run, .. } => known[*player].extend(run.cards().iter().copied())
+            }
+        }
+
+        let mut unseen = self.table.stock.len();
+        let hidden: Vec<usize> = (0..self.players.len())
+            .filter(|&i| i != player)
+            .map(|i| self.players[i].hand.0.len() - known[i].len().min(self.players[i].hand.0.len()))
+            .collect();
+        unseen += hidden.iter().sum::<usize>();
+
+        let mut size: u128 = 1;
+        for h in hidden {
+            size = match binomial(unseen, h).and_then(|ways| size.checked_mul(ways)) {
+                Some(size) => size,
+                None => return u128::MAX
+            };
+            unseen -= h;
+        }
+
+        size
+    }
+
+    /// Projects how many cards every player will hold once the current turn is
+    /// over, assuming every turn is a draw followed by a discard, which leaves
+    /// the hand size unchanged. Melds and layoffs are ignored, so this is an
+    /// upper bound for players who still meld, and so is running out of cards
+    /// to draw.
+    ///
+    /// Since no turn changes a hand size, the projection holds for any amount
+    /// of turns ahead, so it doesn't take one. The only change is for the
+    /// current player if they've already drawn: their discard still has to
+    /// come, so they'll hold one card fewer.
+    pub fn projected_hand_sizes(&self) -> Vec<usize> {
+        let mut sizes: Vec<usize> = self.players.iter().map(|p| p.hand.0.len()).collect();
+        if self.phase == TurnPhase::Play {
+            sizes[self.current_player] = sizes[self.current_player].saturating_sub(1);
+        }
+
+        sizes
+    }
+
+    /// Counts the jokers that the given player can't see: the ones in the deck
+    /// the game was dealt from (see the deck field) that aren't on the table
+    /// (see Table::visible_jokers()) or in their own hand. These are either in
+    /// the stock or in an opponent's hand.
+    pub fn jokers_unaccounted(&self, player: usize) -> usize {
+        let total = self.deck.iter().filter(|c| c.card_type == CardType::Joker).count();
+        let held = self.players[player].hand.0.iter().filter(|c| c.card_type == CardType::Joker).count();
+
+        total.saturating_sub(self.table.visible_jokers() + held)
+    }
+
+    /// Returns what the current player gets to see.
+    pub fn view(&self) -> PlayerView<'_> {
+        PlayerView {
+            hand: &self.players[self.current_player].hand,
+            melds: &self.table.melds,
+            discard_top: self.table.peek_discard().copied(),
+            stock_count: self.table.stock.len(),
+            hand_sizes: self.players.iter().map(|p| p.hand.0.len()).collect()
+        }
+    }
+
+    /// Estimates how many more turns can be played before the stock runs out
+    /// and has to be replenished (see Table::replenish_stock()), so every
+    /// player gets about this many divided by the amount of players.
+    ///
+    /// ## Notes
+    /// This is only an estimate: every turn is assumed to draw from the stock,
+    /// while players who pick up the discard leave the stock alone.
+    pub fn estimated_turns_remaining(&self) -> usize {
+        self.table.stock.len()
+    }
+
+    /// Estimates how many more rounds of turns, in which every player draws
+    /// once, the stock supports (see estimated_turns_remaining()). A round
+    /// that the stock runs out halfway through isn't counted.
+    pub fn estimated_rounds_remaining(&self) -> usize {
+        self.estimated_turns_remaining() / self.players.len()
+    }
+
+    /// Lists every move the current player may make right now, which is none
+    /// at all once a player has gone out, and otherwise depends on the phase
+    /// of their turn:
+    /// * before drawing, they may only draw, from the stock (if it isn't
+    ///   empty or can be replenished) or from the discard pile (if it isn't empty or frozen);
+    /// * after drawing, they may discard any card, and meld or lay off as long
+    ///   as they keep at least one card to discard (unless the config lets them
+    ///   go out without discarding, see GameConfig::discard_to_go_out). Before they've opened (i.e.
+    ///   laid down melds for the first time), they can only meld a combination
+    ///   of melds that's good enough to open with (see GameConfig::validate_opening())
+    ///   and can't lay off; after that, they can meld any single meld and lay
+    ///   off any card onto any meld that takes it (see Table::layoff_options()).
+    ///
+    /// ## Notes
+    /// The opening combinations come from Hand::all_partitions(), so this gets
+    /// expensive for hands that hold a lot of melds.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        if self.round_winner().is_some() { return moves; }
+
+        if self.phase == TurnPhase::Draw {
+            if !self.table.stock.is_empty() || self.table.discard_len() > 1 { moves.push(Move::DrawStock); }
+            if self.table.discard_len() > 0 && !self.table.frozen { moves.push(Move::DrawDiscard); }
+            return moves;
+        }
+
+        let player = &self.players[self.current_player];
+        let hand = &player.hand;
+        let keep = self.config.discard_to_go_out;
+
+        if player.melds.is_empty() {
+            for (melds, rest) in hand.all_partitions() {
+                if melds.is_empty() || (rest.is_empty() && keep) || self.config.validate_opening(&melds, hand).is_err() { continue; }
+
+                let meld = Move::Meld(melds);
+                if !moves.contains(&meld) { moves.push(meld); }
+            }
+        } else {
+            moves.extend(hand.find_melds().into_iter()
+                .filter(|run| run.cards().len() < hand.0.len() || !keep)
+                .map(|run| Move::Meld(vec![run])));
+
+            if hand.0.len() > 1 || !keep {
+                for (i, &card) in hand.0.iter().enumerate() {
+                    if hand.0[..i].contains(&card) { continue; }
+                    moves.extend(self.table.layoff_options(&card).into_iter().map(|meld| Move::LayOff { meld, card }));
+                }
+            }
+        }
+
+        for (i, &card) in hand.0.iter().enumerate() {
+            if !hand.0[..i].contains(&card) { moves.push(Move::Discard(card)); }
+        }
+
+        moves
+    }
+
+    /// Returns the move the current player is forced to make right now, if the
+    /// config forces one. That's only ever the case when `config.must_go_out`
+    /// is set and the player, having drawn, can go out with the cards in their
+    /// hand alone (without laying off): the move is then the next step towards
+    /// going out, so applying the returned moves one after the other takes them
+    /// out. That is:
+    /// * the discard of their last card, once that's the only card left;
+    /// * otherwise, if they haven't opened yet, a meld of everything they can
+    ///   go out with, which has to be good enough to open with (see
+    ///   GameConfig::validate_opening());
+    /// * otherwise, the first of those melds.
+    ///
+    /// Whether they need a discard to go out is decided by `config.discard_to_go_out`.
+    /// Nothing ever forces a player to draw from a particular pile.
+    pub fn mandatory_move(&self) -> Option<Move> {
+        if !self.config.must_go_out { return None; }
+
+        self.go_out_move()
+    }
+
+    /// Returns the next step towards going out for the current player if they
+    /// can go out with the cards in their hand alone, whether the config forces
+    /// them to or not (see mandatory_move()).
+    fn go_out_move(&self) -> Option<Move> {
+        if self.phase != TurnPhase::Play || self.round_winner().is_some() { return None; }
+
+        let player = &self.players[self.current_player];
+        let opened = !player.melds.is_empty();
+        let (melds, rest) = player.hand.all_partitions().into_iter().find(|(melds, rest)| {
+            let closes = rest.len() == 1 || (rest.is_empty() && !self.config.discard_to_go_out);
+            closes && (opened || melds.is_empty() || self.config.validate_opening(melds, &player.hand).is_ok())
+        })?;
+
+        match melds.len() {
+            0 => Some(Move::Discard(rest[0])),
+            _ if !opened => Some(Move::Meld(melds)),
+            _ => Some(Move::Meld(vec![melds[0].clone()]))
+        }
+    }
+
+    /// Makes the given move for the current player, after checking that it's
+    /// legal right now (see legal_moves()), and records it in the event log.
+    /// Fails without changing anything if the move isn't legal.
+    ///
+    /// Drawing moves the turn on to the Play phase, and discarding ends the
+    /// turn. If the discarded card was the player's last, they've gone out and
+    /// the round is over, so the turn doesn't pass to the next player. The same
+    /// goes for melding or laying off the last card, if the config allows going
+    /// out that way (see GameConfig::discard_to_go_out). Any move after that
+    /// fails with MoveError::RoundOver.
+    pub fn apply(&mut self, m: Move) -> Result<(), MoveError> {
+        if self.round_winner().is_some() { return Err(MoveError::RoundOver); }
+        let expected = if matches!(m, Move::DrawStock | Move::DrawDiscard) { TurnPhase::Draw } else { TurnPhase::Play };
+        if self.phase != expected { return Err(MoveError::WrongPhase); }
+
+        let current = self.current_player;
+        match m {
+            Move::DrawStock | Move::DrawDiscard => {
+                let (source, card) = if m == Move::DrawStock {
+                    self.table.replenish_stock();
+                    (DrawSource::Stock, self.table.draw_from_stock())
+                } else {
+                    if self.table.frozen { return Err(MoveError::FrozenPile); }
+                    (DrawSource::Discard, self.table.draw_from_discard())
+                };
+                let card = card.ok_or(MoveError::EmptyPile)?;
+
+                self.players[current].hand.0.push(card);
+                self.events.push(GameEvent::Draw { player: current, source, card });
+                self.phase = TurnPhase::Play;
+                self.uncommitted_melds.clear();
+            },
+            Move::Meld(runs) => {
+                // the melds go onto the table in canonical order, however their cards were given
+                let runs = runs.iter()
+                    .map(|run| Run::from_cards(run.kind(), run.cards().to_vec()))
+                    .collect::<Result<Vec<Run>, RunError>>()
+                    .map_err(MoveError::InvalidMeld)?;
+                let mut rest = self.players[current].hand.0.clone();
+                for run in runs.iter() {
+                    for card in run.cards() {
+                        let index = rest.iter().position(|c| c == card).ok_or(MoveError::NotInHand(*card))?;
+                        rest.remove(index);
+                    }
+                }
+                if rest.is_empty() && self.config.discard_to_go_out { return Err(MoveError::MustKeepCard); }
+                if self.players[current].melds.is_empty() {
+                    self.config.validate_opening(&runs, &self.players[current].hand).map_err(MoveError::Opening)?;
+                }
+
+                let first = self.table.melds.len();
+                self.players[current].melds.extend(first..first + runs.len());
+                self.uncommitted_melds.extend(first..first + runs.len());
+                self.players[current].hand.0 = rest;
+                self.table.melds.extend(runs.iter().cloned());
+                self.events.push(GameEvent::Meld { player: current, runs });
+            },
+            Move::LayOff { meld, card } => {
+                let hand = &self.players[current].hand.0;
+                if self.players[current].melds.is_empty() { return Err(MoveError::CannotLayOff); }
+                if !self.table.melds.get(meld).is_some_and(|run| run.can_append(&card)) { return Err(MoveError::CannotLayOff); }
+                let index = hand.iter().position(|&c| c == card).ok_or(MoveError::NotInHand(card))?;
+                if hand.len() < 2 && self.config.discard_to_go_out { return Err(MoveError::MustKeepCard); }
+
+                let mut cards = self.table.melds[meld].cards().to_vec();
+                cards.push(card);
+                self.table.melds[meld] = Run::from_cards(self.table.melds[meld].kind(), cards).unwrap(); // checked by can_append()
+                self.players[current].hand.0.remove(index);
+                self.events.push(GameEvent::LayOff { player: current, meld, card });
+            },
+            Move::Discard(card) => {
+                let hand = &mut self.players[current].hand.0;
+                let index = hand.iter().position(|&c| c == card).ok_or(MoveError::NotInHand(card))?;
+                hand.remove(index);
+                let out = hand.is_empty();
+
+                self.table.discard(card);
+                self.events.push(GameEvent::Discard { player: current, card });
+                self.phase = TurnPhase::Draw;
+                self.uncommitted_melds.clear();
+                if !out { self.current_player = (current + 1) % self.players.len(); }
+            }
+        }
+
+        debug_assert!(self.table.validate_against(&self.deck).is_ok(), "the table is inconsistent: {:?}", self.table.validate_against(&self.deck));
+        Ok(())
+    }
+
+    /// Checks that the current player can make every step of the given plan,
+    /// one after the other, from the start of their turn (see apply()), without
+    /// changing anything. Returns the error of the first step that fails, which
+    /// is MoveError::WrongPhase for a plan that's made halfway through a turn,
+    /// or that holds a draw among its plays.
+    pub fn validate_turn(&self, plan: &TurnPlan) -> Result<(), MoveError> {
+        self.play_plan(plan).map(|_| ())
+    }
+
+    /// Makes every step of the given plan for the current player (see
+    /// validate_turn()), or none of them at all if any of them fails.
+    pub fn apply_turn(&mut self, plan: &TurnPlan) -> Result<(), MoveError> {
+        *self = self.play_plan(plan)?;
+
+        Ok(())
+    }
+
+    /// Plays the given plan out on a copy of this game, which is returned if
+    /// every step succeeds.
+    fn play_plan(&self, plan: &TurnPlan) -> Result<Game, MoveError> {
+        let mut game = self.clone();
+        let draw = match plan.draw { DrawSource::Stock => Move::DrawStock, DrawSource::Discard => Move::DrawDiscard };
+
+        game.apply(draw)?;
+        for play in plan.plays.iter() {
+            if matches!(play, Move::DrawStock | Move::DrawDiscard) { return Err(MoveError::WrongPhase); }
+            game.apply(play.clone())?;
+        }
+        if let Some(card) = plan.discard { game.apply(Move::Discard(card))?; }
+
+        Ok(game)
+    }
+
+    /// Takes back a meld that the current player laid down during their current
+    /// turn (see uncommitted_melds): it's removed from the table, with every
+    /// later meld moving down an index, and its cards (including any that were
+    /// laid off onto it) go back into their hand. This is logged as
+    /// GameEvent::UndoMeld. Once they discard, the turn is committed and none
+    /// of its melds can be taken back anymore.
+    ///
+    /// If the player opened during this turn, the melds they keep still have to
+    /// be good enough to open with (see GameConfig::validate_opening()), unless
+    /// they take back their whole opening, in which case they can't have laid
+    /// off anything during this turn either.
+    pub fn undo_meld(&mut self, meld_index: usize) -> Result<(), MoveError> {
+        if self.round_winner().is_some() { return Err(MoveError::RoundOver); }
+        if self.phase != TurnPhase::Play { return Err(MoveError::WrongPhase); }
+        if !self.uncommitted_melds.contains(&meld_index) { return Err(MoveError::CannotUndo); }
+
+        let current = self.current_player;
+        let player = &self.players[current];
+        let opening = player.melds.iter().all(|i| self.uncommitted_melds.contains(i));
+        if opening {
+            let kept: Vec<Run> = player.melds.iter().filter(|&&i| i != meld_index).map(|&i| self.table.melds[i].clone()).collect();
+            if !kept.is_empty() {
+                // the kept melds already lie on the table, so every card they use is there
+                let held = Hand(kept.iter().flat_map(|r| r.cards().iter().copied()).collect());
+                self.config.validate_opening(&kept, &held).map_err(MoveError::Opening)?;
+            } else {
+                let turn = self.events.iter().rposition(|e| matches!(e, GameEvent::Draw { .. })).map_or(0, |i| i + 1);
+                let laid_off = self.events[turn..].iter().any(|e| matches!(e, GameEvent::LayOff { player: p, .. } if *p == current));
+                if laid_off { return Err(MoveError::CannotUndo); }
+            }
+        }
+
+        let run = self.table.melds.remove(meld_index);
+        let shift = |melds: &mut Vec<usize>| {
+            melds.retain(|&i| i != meld_index);
+            for i in melds.iter_mut() { if *i > meld_index { *i -= 1; } }
+        };
+        for player in self.players.iter_mut() { shift(&mut player.melds); }
+        shift(&mut self.uncommitted_melds);
+
+        self.players[current].hand.0.extend(run.cards().iter().copied());
+        self.events.push(GameEvent::UndoMeld { player: current, meld: meld_index, run });
+
+        Ok(())
+    }
+
+    /// Returns true if the round can't go on because there is nothing left to
+    /// draw: the stock is empty and the discard pile holds at most one card,
+    /// so it can't be turned over to replenish the stock.
+    pub fn is_stalemate(&self) -> bool {
+        self.table.stock.is_empty() && self.table.discard_len() <= 1
+    }
+
+    /// Plays a single turn for the current player, letting the given strategy
+    /// make the decisions. Returns true if the round is over after this turn.
+    ///
+    /// If the stock is empty at the start of the turn, it's replenished from
+    /// the discard pile (see Table::replenish_stock()); if that isn't possible
+    /// either, the round ends in a stalemate. If the player can go out after
+    /// drawing, they do so automatically, by the same rules as any other move
+    /// (see apply()): they have to be able to open if they haven't yet, and to
+    /// discard if the config says so. Everything that happens is recorded in
+    /// the event log.
+    pub fn play_turn(&mut self, strategy: &dyn Strategy) -> bool {
+        self.uncommitted_melds.clear();
+        self.table.replenish_stock();
+        if self.is_stalemate() { return true; }
+
+        let current = self.current_player;
+        let draw = match strategy.choose_draw(&self.view()) {
+            DrawSource::Discard => Move::DrawDiscard,
+            DrawSource::Stock   => Move::DrawStock
+        };
+        if self.apply(draw).is_err() {
+            // the stock isn't empty, so there's always something to draw
+            self.apply(Move::DrawStock).expect("cannot draw from the stock");
+        }
+
+        while let Some(step) = self.go_out_move() {
+            self.apply(step).expect("every step towards going out is legal");
+            if self.players[current].hand.0.is_empty() { return true; }
+        }
+
+        let discarded = strategy.choose_discard(&self.view());
+        self.apply(Move::Discard(discarded)).expect("a strategy chose to discard a card that isn't in its hand");
+
+        self.players[current].hand.0.is_empty()
+    }
+
+    /// Plays turns until the round is over, with `strategies[i]` deciding for
+    /// player i. Returns the index of the player who went out, or None if the
+    /// round ended in a stalemate (see is_stalemate()).
+    pub fn play(&mut self, strategies: &[&dyn Strategy]) -> Option<usize> {
+        assert_eq!(strategies.len(), self.players.len(), "every player needs exactly one strategy");
+
+        loop {
+            let player = self.current_player;
+            if self.play_turn(strategies[player]) {
+                return if self.players[player].hand.0.is_empty() { Some(player) } else { None };
+            }
+        }
+    }
+}
+
+/// Calculates the binomial coefficient C(n, k), or None if it doesn't fit in a u128.
+fn binomial(n: usize, k: usize) -> Option<u128> {
+    let k = k.min(n - k) as u128;
+    let n = n as u128;
+
+    // every partial product is itself a binomial coefficient, so the division is exact
+    (0..k).try_fold(1u128, |acc, i| acc.checked_mul(n - i).map(|x| x / (i + 1)))
+}
@@ -1,17 +1,57 @@
 //! Tbh I only put this in a separate module because it didn't fit anywhere else.
 
-use crate::HAND_SIZE;
-use crate::card::{Card, CardType};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::{HAND_SIZE, OPENING_SCORE};
+use crate::card::{Card, CardType, Suit};
+use crate::rules::{GameConfig, ScoringRules};
+use crate::run::{Run, RunKind, RunCoercionStrategy, enumerate_runs, verify_run};
+use crate::utils::{generate_deck_with_decks, generate_single_deck, take_cards};
+
+/// A struct that holds a hand split up for rendering (see Hand::layout()):
+/// every meld and the deadwood as a separate column of cards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub struct HandLayout {
+    pub melds: Vec<Vec<Card>>,
+    pub deadwood: Vec<Card>
+}
 
 /// A struct that represents a series of cards that a player holds.
 ///
 /// Most functions on Hand mutate either a deck (`Vec<Card>`) or a Table instance.
+#[derive(Debug, Clone)]
 pub struct Hand(pub Vec<Card>);
 impl Hand {
     /// Creates a new hand of thirteen cards by popping them off of a mutable deck.
     pub fn draw(deck: &mut Vec<Card>) -> Self {
-        assert!(deck.len() >= HAND_SIZE.into());
-        Hand((0..HAND_SIZE).map(|_| deck.pop().unwrap()).collect())
+        Hand::draw_n(deck, HAND_SIZE.into()).expect("the deck holds too few cards to draw a hand from")
+    }
+
+    /// Creates a new hand of `n` cards by popping them off of a mutable deck
+    /// (see take_cards()). If the deck holds fewer than `n` cards, it's left
+    /// untouched and the amount of cards it does hold is returned instead.
+    pub fn draw_n(deck: &mut Vec<Card>, n: usize) -> Result<Self, usize> {
+        take_cards(deck, n).map(Hand)
+    }
+
+    /// Encodes this hand in a compact binary format: the first byte holds the
+    /// amount of cards, followed by one byte per card holding its id (see Card::to_id()).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.0.len() as u8];
+        bytes.extend(self.0.iter().map(|c| c.to_id()));
+
+        bytes
+    }
+
+    /// Decodes a hand from the format written by to_bytes(). Returns None if
+    /// the bytes aren't a well-formed encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&len, ids) = bytes.split_first()?;
+        if ids.len() != len as usize { return None; }
+
+        ids.iter().map(|&id| Card::from_id(id)).collect::<Option<Vec<Card>>>().map(Hand)
     }
 
     /// Calculates the total score.
@@ -24,4 +64,615 @@ impl Hand {
         else if self.0.len() == 1 && self.0[0].card_type == CardType::Ace { 1 }
         else { self.0.iter().fold(0u8, |acc, c| acc + c.score()) }
     }
+
+    /// Calculates the total score according to the given rules. This is the
+    /// same as score(), except for jokers counting as `rules.joker_value` and
+    /// a hand of `rules.hand_size` cards counting as `rules.full_hand_penalty`.
+    pub fn score_with(&self, rules: &ScoringRules) -> u16 {
+        if self.0.len() == rules.hand_size.into() { rules.full_hand_penalty }
+        else if self.0.len() == 1 && self.0[0].card_type == CardType::Ace { 1 }
+        else {
+            self.0.iter().fold(0u16, |acc, c| acc + u16::from(c.score_with(rules)))
+        }
+    }
+
+    /// Calculates the score this hand would end a round with if it got to meld
+    /// as well as it could first (see best_partition()): only the deadwood is
+    /// scored, according to the given rules (see score_with()). So a single
+    /// ace left over counts as one. The full hand penalty only applies when
+    /// the hand holds at least `rules.hand_size` cards and can't meld any of
+    /// them, no matter how many cards the deadwood comes down to.
+    pub fn round_score(&self, rules: &ScoringRules) -> u16 {
+        let (melds, deadwood) = self.best_partition_with(rules);
+        if melds.is_empty() && self.0.len() >= rules.hand_size.into() { rules.full_hand_penalty }
+        else if deadwood.len() == 1 && deadwood[0].card_type == CardType::Ace { 1 }
+        else {
+            deadwood.iter().fold(0u16, |acc, c| acc + u16::from(c.score_with(rules)))
+        }
+    }
+
+    /// Calculates the highest total score the melds of this hand could be worth
+    /// (see all_partitions()), with every joker standing for the highest card
+    /// it legally can, and the cards valued according to the given rules (see
+    /// Run::max_score_with()). This is the ceiling for melding, unlike
+    /// round_score(), which looks at the deadwood that's left.
+    pub fn max_meld_score(&self, rules: &ScoringRules) -> u16 {
+        self.all_partitions().iter()
+            .map(|(melds, _)| melds.iter().map(|m| u16::from(m.max_score_with(rules))).sum())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Finds every valid run that can be formed out of the cards in this hand.
+    ///
+    /// ## Notes
+    /// This tries every subset of at least three cards (see enumerate_runs()),
+    /// so it is only meant to be used on hands of a sensible size.
+    pub fn find_melds(&self) -> Vec<Run> {
+        (3..=self.0.len()).flat_map(|len| enumerate_runs(&self.0, len)).collect()
+    }
+
+    /// Finds every set (Run::Equal) that can be formed out of the cards in
+    /// this hand, in the same order as find_melds().
+    pub fn sets(&self) -> Vec<Run> {
+        self.find_melds().into_iter().filter(|run| run.kind() == RunKind::Set).collect()
+    }
+
+    /// Finds every sequence (Run::Ascending) that can be formed out of the
+    /// cards in this hand, in the same order as find_melds().
+    pub fn sequences(&self) -> Vec<Run> {
+        self.find_melds().into_iter().filter(|run| run.kind() == RunKind::Sequence).collect()
+    }
+
+    /// Finds the highest scoring meld this hand can form with a joker in it
+    /// (see find_melds() and Run::get_score()), along with the position the
+    /// joker takes in that meld as it's displayed (see Run::to_display_cards()
+    /// and Run::resolve_jokers()). Returns None if the hand can't form any meld
+    /// with a joker.
+    ///
+    /// Between equally scoring melds, the one with fewer jokers wins, then the
+    /// one that takes the most penalty points out of the hand according to the
+    /// given rules. In a meld with several jokers, the position of the first one
+    /// is returned.
+    pub fn best_joker_placement(&self, rules: &ScoringRules) -> Option<(Run, usize)> {
+        let best = self.find_melds().into_iter()
+            .filter(|run| run.cards().iter().any(|c| c.card_type == CardType::Joker))
+            .max_by_key(|run| {
+                let jokers = run.cards().iter().filter(|c| c.card_type == CardType::Joker).count();
+                let penalty: u16 = run.cards().iter().map(|c| u16::from(c.score_with(rules))).sum();
+                (run.get_score(), std::cmp::Reverse(jokers), penalty, std::cmp::Reverse(run.clone()))
+            })?;
+
+        let (_, represented) = best.resolve_jokers()[0];
+        let position = best.to_display_cards(&RunCoercionStrategy::default()).iter().position(|&c| c == represented).unwrap();
+
+        Some((best, position))
+    }
+
+    /// Returns true if this hand holds every one of the given cards, e.g. to
+    /// check that a player actually has the cards of a meld they propose. A
+    /// card that's given more than once has to be held at least as many times.
+    pub fn contains_all(&self, cards: &[Card]) -> bool {
+        without(&self.0, cards).is_some()
+    }
+
+    /// Finds the melds that the given card would make possible if it were added
+    /// to this hand, i.e. the ones that find_melds() would find in the hand
+    /// with the card included, but not in the hand as it stands.
+    pub fn melds_enabled_by(&self, card: &Card) -> Vec<Run> {
+        let current = self.find_melds();
+        let mut hand = self.clone();
+        hand.0.push(*card);
+
+        hand.find_melds().into_iter().filter(|run| !current.contains(run)).collect()
+    }
+
+    /// Counts how many distinct cards, out of the ones that are still unseen
+    /// (along with how many copies of each), would create or extend a meld if
+    /// they were drawn (see melds_enabled_by()). The higher this is, the more
+    /// options the hand keeps open. Cards without any unseen copies left don't count.
+    pub fn flexibility(&self, unseen: &HashMap<Card, u8>) -> usize {
+        unseen.iter()
+            .filter(|&(card, &copies)| copies > 0 && !self.melds_enabled_by(card).is_empty())
+            .count()
+    }
+
+    /// Finds the single highest scoring meld that can be laid down from this
+    /// hand right now (see find_melds() and Run::get_score()), or None if there
+    /// is no meld at all.
+    ///
+    /// Between equally scoring melds, the one that takes the most penalty
+    /// points out of the hand according to the given rules wins, and then the
+    /// longest one.
+    pub fn best_single_meld(&self, rules: &ScoringRules) -> Option<Run> {
+        self.find_melds().into_iter().max_by_key(|run| {
+            let penalty: u16 = run.cards().iter().map(|c| u16::from(c.score_with(rules))).sum();
+            (run.get_score(), penalty, run.cards().len())
+        })
+    }
+
+    /// Renders this hand for people to read, one suit per line in the standard
+    /// order (spades, diamonds, clubs, hearts), with the cards of every suit
+    /// sorted by rank and rendered by Card::to_unicode(), e.g. "♠A ♠2 ♠K".
+    /// Jokers go on a line of their own at the end, and suits the hand doesn't
+    /// hold are left out. An empty hand renders as an empty string.
+    pub fn pretty(&self) -> String {
+        let mut groups = self.group_by_suit();
+        let lines: Vec<String> = [Suit::Spades, Suit::Diamonds, Suit::Clubs, Suit::Hearts, Suit::JokerSuit].iter()
+            .filter_map(|suit| groups.remove(suit))
+            .map(|mut cards| {
+                cards.sort();
+                cards.iter().map(|c| c.to_unicode()).collect::<Vec<_>>().join(" ")
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+
+    /// Groups the cards in this hand by their suit, keeping them in the order
+    /// they're held in. Jokers are grouped under Suit::JokerSuit.
+    pub fn group_by_suit(&self) -> HashMap<Suit, Vec<Card>> {
+        let mut groups: HashMap<Suit, Vec<Card>> = HashMap::new();
+        for &card in self.0.iter() {
+            groups.entry(card.suit).or_default().push(card);
+        }
+
+        groups
+    }
+
+    /// Returns, for every suit this hand holds real cards of, the amount of
+    /// cards in its longest near-sequence: a sequence of different ranks that
+    /// misses at most a single rank in between (see group_by_suit()). An ace
+    /// can sit at either end, like in a real sequence. Jokers aren't counted.
+    pub fn suit_run_potential(&self) -> HashMap<Suit, usize> {
+        self.group_by_suit().into_iter()
+            .filter(|(suit, _)| *suit != Suit::JokerSuit)
+            .map(|(suit, cards)| {
+                // the ace sits either low or high, never at both ends of the same stretch
+                let longest = [false, true].into_iter().map(|high_ace| {
+                    let mut positions: Vec<u8> = cards.iter().map(|c| match c.card_type {
+                        CardType::Ace if high_ace => 14,
+                        _ => c.get_comparison_value()
+                    }).collect();
+                    positions.sort();
+                    positions.dedup();
+
+                    let mut longest = 0;
+                    let mut start = 0;
+                    for end in 0..positions.len() {
+                        while usize::from(positions[end] - positions[start]) + 1 > end - start + 2 { start += 1; }
+                        longest = longest.max(end - start + 1);
+                    }
+
+                    longest
+                }).max().unwrap();
+
+                (suit, longest)
+            })
+            .collect()
+    }
+
+    /// Finds the longest sequence this hand holds without the help of any
+    /// jokers, i.e. the longest stretch of consecutive cards of a single suit,
+    /// or None if there is no such stretch of at least three cards. An ace can
+    /// sit at either end, like in any sequence, but since it can't be played
+    /// low and high at once, the stretches with a low ace and the ones with a
+    /// high ace are looked for separately.
+    ///
+    /// Between equally long sequences, the highest scoring one wins, and then
+    /// the one that sorts first (see Run).
+    pub fn longest_ascending_run(&self) -> Option<Run> {
+        let mut candidates: Vec<Run> = Vec::new();
+
+        for (suit, cards) in self.group_by_suit() {
+            if suit == Suit::JokerSuit { continue; }
+
+            for high_ace in [false, true] {
+                let mut positioned: Vec<(u8, Card)> = cards.iter().map(|&c| match c.card_type {
+                    CardType::Ace if high_ace => (14, c),
+                    _ => (c.get_comparison_value(), c)
+                }).collect();
+                positioned.sort_by_key(|&(p, _)| p);
+                positioned.dedup_by_key(|&mut (p, _)| p);
+
+                let mut start = 0;
+                for end in 1..=positioned.len() {
+                    if end < positioned.len() && positioned[end].0 == positioned[end - 1].0 + 1 { continue; }
+
+                    if end - start >= 3 {
+                        let stretch = positioned[start..end].iter().map(|&(_, c)| c).collect();
+                        if let Ok(run) = verify_run(stretch) { candidates.push(run); }
+                    }
+                    start = end;
+                }
+            }
+        }
+
+        candidates.into_iter().max_by_key(|run| (run.cards().len(), run.get_score(), std::cmp::Reverse(run.clone())))
+    }
+
+    /// Splits the hand into melds and deadwood (the cards that don't fit in any meld).
+    ///
+    /// Out of all partitions (see all_partitions()), this picks the one that
+    /// leaves the least points in deadwood, and then the fewest deadwood cards.
+    pub fn best_partition(&self) -> (Vec<Run>, Vec<Card>) {
+        self.best_partition_with(&ScoringRules::default())
+    }
+
+    /// Same as best_partition(), but with the deadwood scored according to the
+    /// given rules.
+    pub fn best_partition_with(&self, rules: &ScoringRules) -> (Vec<Run>, Vec<Card>) {
+        self.all_partitions().into_iter()
+            .min_by_key(|(_, deadwood)| (deadwood.iter().map(|c| u16::from(c.score_with(rules))).sum::<u16>(), deadwood.len()))
+            .unwrap() // there's always at least the partition without any melds
+    }
+
+    /// Returns every way to split the hand into melds and deadwood, including
+    /// the one without any melds at all. No two partitions hold the same melds.
+    ///
+    /// ## Notes
+    /// The amount of partitions grows exponentially with the amount of melds
+    /// that can be formed. Only melds whose cards are all still left over are
+    /// ever tried, and melds are only picked in the order find_melds() returns
+    /// them, which keeps this manageable for hands of a normal size.
+    pub fn all_partitions(&self) -> Vec<(Vec<Run>, Vec<Card>)> {
+        let melds = self.find_melds();
+        let mut partitions = Vec::new();
+        collect_partitions(&melds, 0, &mut Vec::new(), self.0.clone(), &mut partitions);
+
+        partitions
+    }
+
+    /// Splits the hand into its best melds and its deadwood (see
+    /// best_partition()), ready to be drawn in columns. The melds come
+    /// sequences first, in the order runs are sorted in, and the cards within
+    /// them in their canonical order (see verify_run()). The deadwood is sorted.
+    pub fn layout(&self) -> HandLayout {
+        let (mut melds, mut deadwood) = self.best_partition();
+        melds.sort();
+        deadwood.sort();
+
+        HandLayout {
+            melds: melds.into_iter().map(|run| run.cards().to_vec()).collect(),
+            deadwood
+        }
+    }
+
+    /// Returns the amount of cards that aren't part of any meld in the best
+    /// partition (see best_partition()).
+    pub fn deadwood_count(&self) -> usize {
+        self.best_partition().1.len()
+    }
+
+    /// Returns the total score of the cards that aren't part of any meld in the
+    /// best partition (see best_partition()), according to the given rules.
+    pub fn deadwood_score(&self, rules: &ScoringRules) -> u16 {
+        self.best_partition().1.iter().map(|c| u16::from(c.score_with(rules))).sum()
+    }
+
+    /// Lists every combination of melds this hand could open with, i.e. every
+    /// combination worth at least `threshold` points (see Run::get_score())
+    /// that still leaves a card to discard. No combination is listed twice, and
+    /// the melds within one are sorted (see Run).
+    ///
+    /// The most efficient openings come first: the ones worth the fewest points,
+    /// since those use up the fewest high cards, then the ones that use the
+    /// fewest cards, which keeps the hand flexible, and then the ones that take
+    /// the most penalty points out of the hand according to the given rules.
+    ///
+    /// ## Notes
+    /// The combinations come from all_partitions(). Whether jokers are allowed
+    /// in an opening isn't checked here (see GameConfig::validate_opening()).
+    pub fn opening_options(&self, threshold: u16, rules: &ScoringRules) -> Vec<Vec<Run>> {
+        let mut options: Vec<Vec<Run>> = Vec::new();
+        for (mut melds, rest) in self.all_partitions() {
+            if rest.is_empty() || melds.iter().map(|r| u16::from(r.get_score())).sum::<u16>() < threshold { continue; }
+
+            melds.sort();
+            if !options.contains(&melds) { options.push(melds); }
+        }
+
+        options.sort_by_key(|melds| {
+            let cards = melds.iter().flat_map(|r| r.cards());
+            (
+                melds.iter().map(|r| u16::from(r.get_score())).sum::<u16>(),
+                cards.clone().count(),
+                std::cmp::Reverse(cards.map(|c| u16::from(c.score_with(rules))).sum::<u16>())
+            )
+        });
+
+        options
+    }
+
+    /// Estimates how many cards this hand still has to draw before its melds
+    /// are worth enough to open with (see OPENING_SCORE), or 0 if it can open
+    /// already.
+    ///
+    /// The heuristic goes as follows, starting from the melds in the best
+    /// partition (see best_partition()):
+    /// 1. every two deadwood cards that one more card turns into a meld cost
+    ///    one card, and are worth as much as the best meld they could become.
+    ///    The most valuable ones are counted first, and no card is counted twice;
+    /// 2. every deadwood card left over costs two cards, and is worth as much as
+    ///    a set of three of its rank. Lone jokers aren't counted, since their
+    ///    worth depends entirely on the cards they end up with;
+    /// 3. anything still missing after that costs three cards for every thirty
+    ///    points, the worth of a set of three kings.
+    pub fn opening_gap(&self) -> u16 {
+        let (melds, deadwood) = self.best_partition();
+        let mut points: u16 = melds.iter().map(|r| u16::from(r.get_score())).sum();
+        let mut gap = 0;
+
+        let mut used = vec![false; deadwood.len()];
+        for (score, i, j) in near_melds(&deadwood) {
+            if points >= OPENING_SCORE { return gap; }
+            if used[i] || used[j] { continue; }
+
+            used[i] = true;
+            used[j] = true;
+            points += score;
+            gap += 1;
+        }
+
+        let mut singles: Vec<u16> = deadwood.iter().zip(used)
+            .filter(|&(c, used)| !used && c.card_type != CardType::Joker)
+            .map(|(c, _)| 3 * u16::from(c.score()))
+            .collect();
+        singles.sort_by_key(|&score| std::cmp::Reverse(score));
+
+        for score in singles {
+            if points >= OPENING_SCORE { return gap; }
+
+            points += score;
+            gap += 2;
+        }
+
+        gap + 3 * OPENING_SCORE.saturating_sub(points).div_ceil(30)
+    }
+
+    /// Estimates how many swaps (drawing a card and discarding another) this
+    /// hand is away from going out, much like shanten in mahjong: 0 if it can
+    /// go out as it stands (see can_go_out()), 1 if there is a card that lets
+    /// it go out once drawn, and more the further away it is.
+    ///
+    /// The first two cases are exact. Beyond those, the estimate comes from the
+    /// deadwood of the best partition (see best_partition()): every two
+    /// deadwood cards that one more card turns into a meld take a swap to
+    /// complete, and every other deadwood card but one (which is left over to
+    /// discard when going out) takes a swap to replace. Since each swap brings
+    /// in one card and takes out another, the estimate is the larger of the two
+    /// amounts, but never less than 2.
+    pub fn distance_to_out(&self) -> usize {
+        if self.can_go_out() { return 0; }
+
+        let mut distinct = generate_single_deck(false);
+        distinct.dedup();
+        let one_away = distinct.iter().any(|&card| {
+            let mut hand = self.clone();
+            hand.0.push(card);
+            hand.can_go_out()
+        });
+        if one_away { return 1; }
+
+        let (_, deadwood) = self.best_partition();
+        let mut used = vec![false; deadwood.len()];
+        let mut pairs = 0;
+        for (_, i, j) in near_melds(&deadwood) {
+            if used[i] || used[j] { continue; }
+
+            used[i] = true;
+            used[j] = true;
+            pairs += 1;
+        }
+        let singles = deadwood.len() - 2 * pairs;
+
+        pairs.max(singles.saturating_sub(1)).max(2)
+    }
+
+    /// Returns true if this hand is so far from opening that the player is
+    /// essentially stuck: it doesn't hold a single meld, and it's estimated to
+    /// need more than `threshold` cards before it can open (see opening_gap()).
+    pub fn is_stuck(&self, threshold: u16) -> bool {
+        self.find_melds().is_empty() && self.opening_gap() > threshold
+    }
+
+    /// Returns the cards that make up the melds of the best partition when the
+    /// deadwood is scored according to the given rules (see best_partition()),
+    /// i.e. the cards that shouldn't ever be discarded. This is everything but
+    /// the deadwood, in the order of the melds.
+    pub fn core_cards(&self, rules: &ScoringRules) -> Vec<Card> {
+        self.best_partition_with(rules).0.iter().flat_map(|run| run.cards().iter().copied()).collect()
+    }
+
+    /// Compares two hands for ranking them at the end of a round. A hand that
+    /// ranks better than the other is considered to be Less.
+    ///
+    /// The tie-break chain is as follows:
+    /// 1. the lower score (see score_with()) wins;
+    /// 2. when the scores are equal, the hand with more melds (in its best
+    ///    partition under the same rules, see best_partition_with()) wins;
+    /// 3. when those are equal too, the hand with fewer deadwood cards wins.
+    pub fn cmp_for_ranking(&self, other: &Hand, rules: &ScoringRules) -> Ordering {
+        let (self_melds, self_deadwood) = self.best_partition_with(rules);
+        let (other_melds, other_deadwood) = other.best_partition_with(rules);
+
+        self.score_with(rules).cmp(&other.score_with(rules))
+            .then(other_melds.len().cmp(&self_melds.len()))
+            .then(self_deadwood.len().cmp(&other_deadwood.len()))
+    }
+
+    /// Returns true if the hand can be melded completely, save for at most one
+    /// card that can be discarded (see closing_discards()). Like closing_discards(),
+    /// this tries every possible way of melding the hand, since the best
+    /// partition is the one that leaves the fewest points, not the fewest cards.
+    pub fn can_go_out(&self) -> bool {
+        !self.0.is_empty() && melds_leaving(&self.find_melds(), &self.0, 1)
+    }
+
+    /// Returns true if the hand can go out under the given config. If
+    /// `config.discard_to_go_out` is set, going out takes a discard, so there
+    /// has to be a closing discard (see closing_discards()); if it isn't, it's
+    /// also enough for the hand to meld completely.
+    pub fn can_go_out_with(&self, config: &GameConfig) -> bool {
+        if self.0.is_empty() { return false; }
+
+        (!config.discard_to_go_out && melds_completely(&self.0)) || !self.closing_discards().is_empty()
+    }
+
+    /// Suggests which card to discard: the highest scoring card that isn't
+    /// part of a meld, or the highest scoring card overall if there is no such
+    /// card. Returns None if the hand is empty.
+    pub fn suggest_discard(&self) -> Option<Card> {
+        let (_, deadwood) = self.best_partition();
+        let candidates = if deadwood.is_empty() { &self.0 } else { &deadwood };
+
+        candidates.iter().copied().max_by_key(|c| c.score())
+    }
+
+    /// Evaluates how good this hand would be with the candidate card added to
+    /// it, e.g. to compare what drawing different cards would do.
+    ///
+    /// The value is the total score of the melds in the best partition under
+    /// the given rules (see best_partition_with() and Run::get_score()) minus
+    /// the score of the deadwood according to those rules, so higher is better.
+    pub fn evaluate_with(&self, candidate: Card, rules: &ScoringRules) -> i32 {
+        let mut hand = self.clone();
+        hand.0.push(candidate);
+
+        hand.evaluate(rules)
+    }
+
+    /// Quantifies how much taking the given top of the discard pile would
+    /// improve this hand: how much better it evaluates with the card than
+    /// without it (see evaluate_with()). A card that doesn't fit into any meld
+    /// only adds to the deadwood, so it has a negative value.
+    pub fn discard_benefit(&self, top: &Card, rules: &ScoringRules) -> i32 {
+        self.evaluate_with(*top, rules) - self.evaluate(rules)
+    }
+
+    /// Returns true if taking the given top of the discard pile would improve
+    /// this hand (see discard_benefit()), e.g. for a bot to decide where to
+    /// draw from.
+    pub fn would_benefit_from_discard(&self, top: &Card, rules: &ScoringRules) -> bool {
+        self.discard_benefit(top, rules) > 0
+    }
+
+    /// Evaluates how good this hand is as it stands (see evaluate_with()).
+    fn evaluate(&self, rules: &ScoringRules) -> i32 {
+        let (melds, deadwood) = self.best_partition_with(rules);
+        let melded: i32 = melds.iter().map(|r| i32::from(r.get_score())).sum();
+        let dead: i32 = deadwood.iter().map(|c| i32::from(c.score_with(rules))).sum();
+
+        melded - dead
+    }
+
+    /// Finds the cards that would improve this hand the most if drawn (see
+    /// evaluate_with()), best first. Cards of which the hand already holds
+    /// every copy in the deck of `rules.deck_count` single decks are skipped
+    /// (see generate_deck_with_decks()), as are cards that wouldn't
+    /// improve the hand at all. Equally good cards are kept in deck order, so
+    /// real cards come before jokers.
+    pub fn most_wanted_cards(&self, rules: &ScoringRules) -> Vec<Card> {
+        let current = self.evaluate(rules);
+        let deck = generate_deck_with_decks(rules.deck_count.into(), false);
+
+        let mut candidates: Vec<(Card, i32)> = Vec::new();
+        for card in deck.iter() {
+            if candidates.iter().any(|(c, _)| c == card) { continue; }
+            if self.0.iter().filter(|&c| c == card).count() >= deck.iter().filter(|&c| c == card).count() { continue; }
+
+            let improvement = self.evaluate_with(*card, rules) - current;
+            if improvement > 0 { candidates.push((*card, improvement)); }
+        }
+
+        candidates.sort_by_key(|&(_, improvement)| std::cmp::Reverse(improvement));
+        candidates.into_iter().map(|(c, _)| c).collect()
+    }
+
+    /// Returns every card that the player can discard to go out, i.e. every
+    /// card that leaves the rest of the hand completely melded when removed.
+    /// If this is empty, the player can't go out by discarding, which is the
+    /// only way to go out if `GameConfig::discard_to_go_out` is set.
+    ///
+    /// Unlike best_partition(), this tries every possible way of melding the
+    /// remaining cards, so it never misses a way to go out.
+    pub fn closing_discards(&self) -> Vec<Card> {
+        let mut discards: Vec<Card> = Vec::new();
+
+        for (i, &card) in self.0.iter().enumerate() {
+            if discards.contains(&card) { continue; }
+
+            let mut rest = self.0.clone();
+            rest.remove(i);
+            if melds_completely(&rest) { discards.push(card); }
+        }
+
+        discards
+    }
+}
+
+/// Finds every two of the given cards that one more card would turn into a
+/// meld, as triples of the score of the best meld they could become and their
+/// indices, highest score first.
+fn near_melds(cards: &[Card]) -> Vec<(u16, usize, usize)> {
+    let deck = generate_single_deck(false);
+    let mut pairs: Vec<(u16, usize, usize)> = Vec::new();
+
+    for i in 0..cards.len() {
+        for j in i + 1..cards.len() {
+            let best = deck.iter()
+                .filter_map(|&c| verify_run(vec![cards[i], cards[j], c]).ok())
+                .map(|r| u16::from(r.get_score()))
+                .max();
+            if let Some(score) = best { pairs.push((score, i, j)); }
+        }
+    }
+    pairs.sort_by_key(|&(score, _, _)| std::cmp::Reverse(score));
+
+    pairs
+}
+
+/// Checks whether the given cards can be split up into melds without any
+/// deadwood (see melds_leaving()).
+fn melds_completely(cards: &[Card]) -> bool {
+    melds_leaving(&Hand(cards.to_vec()).find_melds(), cards, 0)
+}
+
+/// Checks whether the given cards can be split up into the given melds with at
+/// most `spare` cards left over, by trying every meld the first card could be
+/// part of, and leaving it over if there are spare cards left.
+fn melds_leaving(melds: &[Run], cards: &[Card], spare: usize) -> bool {
+    let Some(first) = cards.first() else { return true; };
+
+    melds.iter()
+        .filter(|run| run.cards().contains(first))
+        .any(|run| without(cards, run.cards()).is_some_and(|rest| melds_leaving(melds, &rest, spare)))
+        || (spare > 0 && melds_leaving(melds, &cards[1..], spare - 1))
+}
+
+/// Adds the partition made of `chosen` and `rest`, and every partition that
+/// can be made by picking more melds (from index `from` onwards) out of `rest`.
+/// Since a meld can be picked again, melds that the hand holds twice are found too.
+fn collect_partitions(melds: &[Run], from: usize, chosen: &mut Vec<Run>, rest: Vec<Card>, partitions: &mut Vec<(Vec<Run>, Vec<Card>)>) {
+    for (i, meld) in melds.iter().enumerate().skip(from) {
+        if let Some(remaining) = without(&rest, meld.cards()) {
+            chosen.push(meld.clone());
+            collect_partitions(melds, i, chosen, remaining, partitions);
+            chosen.pop();
+        }
+    }
+
+    partitions.push((chosen.clone(), rest));
+}
+
+/// Removes one copy of every card in `removed` from `cards`, or returns None
+/// if not all of them are there.
+fn without(cards: &[Card], removed: &[Card]) -> Option<Vec<Card>> {
+    let mut rest = cards.to_vec();
+    for card in removed {
+        let index = rest.iter().position(|c| c == card)?;
+        rest.remove(index);
+    }
+
+    Some(rest)
 }
@@ -8,9 +8,26 @@ extern crate rand;
 /// The amount of cards dealt to create a Hand at the beginning of the game.
 pub const HAND_SIZE: u8 = 13;
 
+/// The amount of cards in a single deck: 52 normal cards and 2 jokers.
+pub const SINGLE_DECK_SIZE: usize = 54;
+
+/// The amount of cards in the full playing deck, which consists of two single decks.
+pub const FULL_DECK_SIZE: usize = 2 * SINGLE_DECK_SIZE;
+
+/// The amount of points a joker counts for by default.
+pub const DEFAULT_JOKER_SCORE: u8 = 25;
+
+/// The minimum total score of the melds a player lays down the first time
+/// they lay down any (which is called opening).
+pub const OPENING_SCORE: u16 = 40;
+
 pub mod card;
 pub mod hand;
 pub mod utils;
 pub mod run;
+pub mod rules;
+pub mod table;
+pub mod strategy;
+pub mod game;
 
 pub mod tests;
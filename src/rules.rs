@@ -0,0 +1,164 @@
+//! The rules module holds everything that can be configured about the game.
+//! This entails the ScoringRules and GameConfig structs, the InitialJoker enum,
+//! as well as the OpeningError enum for melds that a player may not open with.
+
+use std::fmt;
+
+use crate::{DEFAULT_JOKER_SCORE, HAND_SIZE, OPENING_SCORE};
+use crate::card::{Card, CardType};
+use crate::hand::Hand;
+use crate::run::{Run, shared_card};
+
+/// A struct that determines how cards and hands are scored.
+///
+/// The Default implementation follows the traditional Ramino rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoringRules {
+    /// The amount of points a joker counts for when it's left in a hand.
+    pub joker_value: u8,
+    /// The amount of points that is taken off the total of the player who goes
+    /// out, without going below zero. Traditionally, this is zero.
+    pub winner_bonus: u16,
+    /// The amount of points a hand counts for as a whole when it still holds
+    /// as many cards as it was dealt, i.e. when the player never melded.
+    pub full_hand_penalty: u16,
+    /// The amount of cards a hand is dealt with, which decides when the full
+    /// hand penalty applies.
+    pub hand_size: u8,
+    /// The amount of points that is taken off the total of a player who goes
+    /// out with a perfect close (see Game::is_perfect_close()), on top of the
+    /// winner bonus and without going below zero. Traditionally, this is zero.
+    pub perfect_close_bonus: u16,
+    /// The amount of single decks the game is played with, which the other
+    /// values are meant for (see for_deck_count()). Traditionally, this is two.
+    pub deck_count: u8
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules::for_deck_count(2)
+    }
+}
+
+impl ScoringRules {
+    /// Returns the rules for a game played with `n` single decks. These are
+    /// the traditional rules, save for the full hand penalty: it's fifty
+    /// points for every deck, so a game with a single deck (and so half the
+    /// cards to meld with) penalises a hand that never melded with 50 points
+    /// rather than 100. With two decks, this is the Default implementation.
+    pub fn for_deck_count(n: usize) -> Self {
+        let deck_count = n.min(u8::MAX.into()) as u8;
+
+        ScoringRules {
+            joker_value: DEFAULT_JOKER_SCORE,
+            winner_bonus: 0,
+            full_hand_penalty: 50 * u16::from(deck_count),
+            hand_size: HAND_SIZE,
+            perfect_close_bonus: 0,
+            deck_count
+        }
+    }
+}
+
+/// A struct that holds everything that can be configured about a game,
+/// including the rules it's scored by.
+///
+/// The Default implementation follows the traditional Ramino rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameConfig {
+    /// The amount of jokers that are added to every single deck.
+    pub jokers_per_deck: u8,
+    /// The rules that cards and hands are scored by.
+    pub scoring: ScoringRules,
+    /// Whether the melds a player opens with may hold jokers. Traditionally,
+    /// they may.
+    pub jokers_allowed_in_opening: bool,
+    /// What happens when the card that's turned face-up to start the discard
+    /// pile is a joker. By default, it's buried.
+    pub initial_joker: InitialJoker,
+    /// Whether a joker that lands on the discard pile freezes it (see
+    /// Table::frozen). Traditionally, it doesn't.
+    pub jokers_freeze_discard: bool,
+    /// Whether a player has to end with a discard to go out, or may also go
+    /// out by melding every last card (see Hand::can_go_out_with()). By
+    /// default, they have to discard.
+    pub discard_to_go_out: bool,
+    /// Whether a player who can go out has to (see Game::mandatory_move()).
+    /// Traditionally, they don't.
+    pub must_go_out: bool
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            jokers_per_deck: 2,
+            scoring: ScoringRules::default(),
+            jokers_allowed_in_opening: true,
+            initial_joker: InitialJoker::Bury,
+            jokers_freeze_discard: false,
+            discard_to_go_out: true,
+            must_go_out: false
+        }
+    }
+}
+
+/// An enum to represent what happens when a joker is turned face-up to start
+/// the discard pile with, right after dealing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitialJoker {
+    /// The joker stays on the discard pile, free for the first player to take.
+    Allow,
+    /// The joker is put at the bottom of the stock, and the next card is turned
+    /// over instead, until that isn't a joker.
+    Bury
+}
+
+/// An enum to represent the reasons why a player can't open with some melds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpeningError {
+    /// The melds are worth fewer points than it takes to open (see OPENING_SCORE).
+    TooFewPoints {
+        /// The amount of points the melds are worth.
+        scored: u16
+    },
+    /// One of the melds holds a joker, which the config doesn't allow.
+    JokerNotAllowed,
+    /// The contained card appears in more of the melds than the hand they're
+    /// laid down from holds copies of it (see GameConfig::validate_opening()).
+    OverlappingMelds(Card)
+}
+
+impl fmt::Display for OpeningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpeningError::TooFewPoints { scored } =>
+                write!(f, "opening takes melds worth at least {} points, but these are only worth {}", OPENING_SCORE, scored),
+            OpeningError::JokerNotAllowed => write!(f, "the melds a player opens with may not hold any jokers"),
+            OpeningError::OverlappingMelds(c) => write!(f, "melds cannot share cards, but {:?} appears in more of them than the hand holds", c)
+        }
+    }
+}
+
+impl std::error::Error for OpeningError {}
+
+impl GameConfig {
+    /// Checks whether a player may open with the given melds from the given
+    /// hand, which are assumed to be valid (see verify_run()): together they
+    /// have to be worth at least OPENING_SCORE points (see Run::get_score()),
+    /// and none of them may hold a joker unless `jokers_allowed_in_opening` is
+    /// set. They may only share a card if the hand holds a copy of it for every
+    /// meld (see melds_are_disjoint()); whether the hand holds the cards at all
+    /// isn't checked here (see Hand::contains_all()).
+    pub fn validate_opening(&self, runs: &[Run], hand: &Hand) -> Result<(), OpeningError> {
+        if let Some(card) = shared_card(runs, &hand.0) { return Err(OpeningError::OverlappingMelds(card)); }
+
+        if !self.jokers_allowed_in_opening && runs.iter().any(|r| r.cards().iter().any(|c| c.card_type == CardType::Joker)) {
+            return Err(OpeningError::JokerNotAllowed);
+        }
+
+        let scored: u16 = runs.iter().map(|r| u16::from(r.get_score())).sum();
+        if scored < OPENING_SCORE { return Err(OpeningError::TooFewPoints { scored }); }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,25 @@
-//! Everything that has to do with runs of cards. This includes the Run enum,
-//! the RunCoercionStrategy struct and the verify_run function.
+//! Everything that has to do with runs of cards. This includes the Run, RunKind
+//! and RunError enums, the RunCoercionStrategy struct and the verify_run,
+//! verify_run_opts, melds_are_disjoint and enumerate_runs functions.
 
-use crate::card::{Card, Suit};
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::card::{Card, CardType, Suit};
+use crate::rules::ScoringRules;
+use crate::utils::{generate_single_deck, validate_card_budget};
 
 /// A Run of cards describes a sequence of cards as it could appear on the table.
 ///
 /// Note that upon creation, this can be an invalid sequence (e.g. [♤2, ♤3, ♤5]).
 /// The verify function needs to be called to ensure validity.
-#[derive(PartialEq, Eq, Debug)]
+///
+/// Runs are ordered by their variant first (Ascending before Equal), and then
+/// by their cards, one by one. Since verify_run() sorts the cards into a
+/// canonical order, this doesn't depend on the order the cards were passed in,
+/// so runs can be used as keys in a BTreeMap or a HashMap alike.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone)]
 pub enum Run {
     /// An Ascending run is one that takes cards of the same suit but sequentially higher cards.
     Ascending(Vec<Card>),
@@ -15,6 +27,57 @@ pub enum Run {
     Equal(Vec<Card>)
 }
 
+/// An enum to represent the two kinds of runs, without any cards: a Sequence
+/// is an Ascending run and a Set is an Equal run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum RunKind {
+    Sequence,
+    Set
+}
+
+/// An enum to represent the reasons why a series of cards can't form a Run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunError {
+    /// A run has to consist of at least three cards.
+    TooShort,
+    /// The contained card, which isn't a joker, appears more than once.
+    DuplicateCard(Card),
+    /// There are too few real cards to tell whether the run is ascending or equal.
+    Ambiguous,
+    /// The cards form neither an ascending nor an equal run.
+    Invalid,
+    /// The real cards don't all share a rank, so the run would have to be
+    /// ascending, but they don't all share a suit either.
+    MixedSuits,
+    /// The run is ascending, but holds more cards than there are positions in
+    /// a suit (from a low ace all the way up to a high ace).
+    TooLong,
+    /// The card at the given index was expected to be a joker, but isn't (or
+    /// there is no card at that index at all).
+    NotAJoker,
+    /// The run uses more copies of the contained card than the deck holds
+    /// (see verify_run_opts()).
+    OverBudget(Card)
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::TooShort         => write!(f, "a run must consist of at least three cards"),
+            RunError::DuplicateCard(c) => write!(f, "a run cannot contain duplicate cards, but {:?} appears twice", c),
+            RunError::Ambiguous        => write!(f, "a run needs at least two real cards to tell what kind of run it is"),
+            RunError::Invalid          => write!(f, "the cards form neither an ascending nor an equal run"),
+            RunError::MixedSuits       => write!(f, "the cards of an ascending run must all be of the same suit"),
+            RunError::TooLong          => write!(f, "an ascending run cannot hold more than {} cards", MAX_SEQUENCE_LEN),
+            RunError::NotAJoker        => write!(f, "there is no joker at the given index"),
+            RunError::OverBudget(c)    => write!(f, "the run uses more copies of {:?} than the deck holds", c)
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
 /// A struct to determine how Runs should be coerced.
 ///
 /// ### Fields
@@ -32,50 +95,493 @@ pub enum Run {
 /// **With `prefer_ascending = false` and `suit_preference = [Clubs, Diamonds, Spades, Hearts]`:**
 ///
 /// [JOKER, ♧Q, ♡Q] → [♢Q, ♧Q, ♡Q]
+///
+/// ### Resolution order
+///
+/// Coercion is always deterministic. The kind of run is decided first (by
+/// `prefer_ascending`, but only when there's a single real card). Within an
+/// ascending run, the suit is fixed and gaps are filled first, after which
+/// `highest_possible` decides whether the remaining jokers score higher by
+/// extending the run upwards, or lower by extending it downwards. Within an
+/// equal run, every fit scores the same, so `suit_preference` decides; any
+/// suit it doesn't list comes after the listed ones, in the standard order
+/// (spades, diamonds, clubs, hearts).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
 pub struct RunCoercionStrategy {
-    prefer_ascending: bool,
-    highest_possible: bool,
-    suit_preference: [Suit; 4]
+    pub prefer_ascending: bool,
+    pub highest_possible: bool,
+    pub suit_preference: [Suit; 4]
+}
+
+impl Default for RunCoercionStrategy {
+    fn default() -> Self {
+        RunCoercionStrategy {
+            prefer_ascending: true,
+            highest_possible: false,
+            suit_preference: [Suit::Spades, Suit::Diamonds, Suit::Clubs, Suit::Hearts]
+        }
+    }
+}
+
+/// The maximum amount of cards in an ascending run: one for every position
+/// from a low ace up to a high ace (see rank_at()).
+const MAX_SEQUENCE_LEN: usize = 14;
+
+/// Gets the card type that sits at the given position of an ascending run,
+/// where 1 is the low ace and 14 is the high ace.
+fn rank_at(position: u8) -> CardType {
+    match position {
+        1 | 14 => CardType::Ace,
+        11     => CardType::Jack,
+        12     => CardType::Queen,
+        13     => CardType::King,
+        n      => CardType::Number(n)
+    }
 }
 
 impl Run {
+    /// Creates an Ascending run without verifying it (see verify_run()). This
+    /// is only meant for trusted code paths where the cards are already known
+    /// to form a valid run; in debug builds, this is asserted anyway.
+    ///
+    /// The cards should be sorted the way verify_run() sorts them, so that the
+    /// resulting run compares equal to a verified one.
+    pub fn ascending_unchecked(cards: Vec<Card>) -> Self {
+        let run = Run::Ascending(cards);
+        debug_assert!(run.is_valid(), "ascending_unchecked was called on an invalid run: {:?}", run);
+        run
+    }
+
+    /// Creates an Equal run without verifying it (see verify_run()). This is
+    /// only meant for trusted code paths where the cards are already known to
+    /// form a valid run; in debug builds, this is asserted anyway.
+    pub fn equal_unchecked(cards: Vec<Card>) -> Self {
+        let run = Run::Equal(cards);
+        debug_assert!(run.is_valid(), "equal_unchecked was called on an invalid run: {:?}", run);
+        run
+    }
+
+    /// Constructs a run of the given kind from the given cards, or returns a
+    /// RunError if they don't form a valid run of that kind (see verify_run()).
+    ///
+    /// Unlike verify_run(), this doesn't have to guess the kind of the run, so
+    /// a run with only a single real card is accepted too (e.g. [♤K, JOKER,
+    /// JOKER] is both a valid sequence and a valid set). The cards are sorted
+    /// into the same canonical order.
+    pub fn from_cards(kind: RunKind, mut cards: Vec<Card>) -> Result<Run, RunError> {
+        match verify_run(cards.clone()) {
+            Ok(run) if run.kind() == kind => Ok(run),
+            Ok(_) => Err(if kind == RunKind::Sequence { RunError::MixedSuits } else { RunError::Invalid }),
+            Err(RunError::Ambiguous) if cards.iter().any(|c| c.card_type != CardType::Joker) => {
+                cards.sort();
+                match kind {
+                    RunKind::Sequence if cards.len() > MAX_SEQUENCE_LEN => Err(RunError::TooLong),
+                    RunKind::Sequence => Ok(Run::Ascending(cards)),
+                    RunKind::Set if cards.len() > 4 => Err(RunError::Invalid),
+                    RunKind::Set => Ok(Run::Equal(cards))
+                }
+            },
+            Err(error) => Err(error)
+        }
+    }
+
+    /// Returns the kind of this run: Sequence for an Ascending run, Set for an
+    /// Equal run.
+    pub fn kind(&self) -> RunKind {
+        match self {
+            Run::Ascending(_) => RunKind::Sequence,
+            Run::Equal(_)     => RunKind::Set
+        }
+    }
+
+    /// Returns true if this run would pass from_cards() as the same kind of run.
+    pub fn is_valid(&self) -> bool {
+        Run::from_cards(self.kind(), self.cards().to_vec()).is_ok()
+    }
+
+    /// Returns the cards that make up this run.
+    pub fn cards(&self) -> &[Card] {
+        match self {
+            Run::Ascending(cards) | Run::Equal(cards) => cards
+        }
+    }
+
+    /// Returns true if this is a sequence, i.e. an Ascending run.
+    pub fn is_sequence(&self) -> bool {
+        matches!(self, Run::Ascending(_))
+    }
+
+    /// Returns true if this is a set, i.e. an Equal run.
+    pub fn is_set(&self) -> bool {
+        matches!(self, Run::Equal(_))
+    }
+
+    /// Finds out which card every joker in this run stands for according to the
+    /// default RunCoercionStrategy. Returns pairs of the index of a joker in
+    /// cards() and the card it stands for.
+    pub fn resolve_jokers(&self) -> Vec<(usize, Card)> {
+        let mut represented = self.to_display_cards(&RunCoercionStrategy::default());
+        for card in self.cards().iter().filter(|c| c.card_type != CardType::Joker) {
+            if let Some(index) = represented.iter().position(|c| c == card) { represented.remove(index); }
+        }
+
+        self.cards().iter().enumerate()
+            .filter(|(_, c)| c.card_type == CardType::Joker)
+            .map(|(i, _)| i)
+            .zip(represented)
+            .collect()
+    }
+
+    /// Finds a joker in this run that the given card could take the place of,
+    /// and returns its index in cards(). A joker that stands for exactly this
+    /// card (see resolve_jokers()) is preferred, but any joker will do as long
+    /// as the run stays valid with the card in its place.
+    pub fn swappable_joker(&self, card: &Card) -> Option<usize> {
+        if card.card_type == CardType::Joker { return None; }
+
+        let jokers = self.resolve_jokers();
+        jokers.iter()
+            .find(|(_, represented)| represented == card)
+            .map(|&(i, _)| i)
+            .or_else(|| jokers.iter().map(|&(i, _)| i).find(|&i| self.swapped(i, *card).is_ok()))
+    }
+
+    /// Puts the given card in the place of the joker at the given index, and
+    /// returns the joker that was freed up. Fails without changing the run if
+    /// there is no joker at that index or if the run would become invalid.
+    pub fn swap_joker(&mut self, index: usize, card: Card) -> Result<Card, RunError> {
+        let run = self.swapped(index, card)?;
+        let joker = self.cards()[index];
+        *self = run;
+
+        Ok(joker)
+    }
+
+    /// Returns a copy of this run, but with the joker at the given index
+    /// replaced by the given card.
+    fn swapped(&self, index: usize, card: Card) -> Result<Run, RunError> {
+        match self.cards().get(index) {
+            Some(c) if c.card_type == CardType::Joker => (),
+            _ => return Err(RunError::NotAJoker)
+        }
+
+        let mut cards = self.cards().to_vec();
+        cards[index] = card;
+
+        Run::from_cards(self.kind(), cards)
+    }
+
+    /// Returns true if the given card can be added to this run while keeping
+    /// it valid and of the same kind.
+    pub fn can_append(&self, card: &Card) -> bool {
+        let mut cards = self.cards().to_vec();
+        cards.push(*card);
+
+        match verify_run(cards) {
+            Ok(run) => run.is_sequence() == self.is_sequence(),
+            Err(_)  => false
+        }
+    }
+
+    /// Returns every card that can be added to this run (see can_append()),
+    /// each one once, in the standard order. A joker is included whenever
+    /// there's room for one.
+    pub fn legal_extensions(&self) -> Vec<Card> {
+        let mut cards: Vec<Card> = generate_single_deck(false).into_iter().filter(|c| self.can_append(c)).collect();
+        cards.sort();
+        cards.dedup();
+
+        cards
+    }
+
+    /// Returns true if this run can't grow any further, i.e. if no card can be
+    /// added to it (see legal_extensions()), like an equal run of all four
+    /// suits. Since an ascending run can go from a low ace to a high ace, one
+    /// that spans A to K still takes a joker as the high ace.
+    pub fn is_maximal(&self) -> bool {
+        self.legal_extensions().is_empty()
+    }
+
+    /// Encodes this run in a compact binary format.
+    ///
+    /// The first byte holds the variant (0 for Ascending, 1 for Equal), the
+    /// second byte the amount of cards, followed by one byte per card holding
+    /// its id (see Card::to_id()).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let tag = match self { Run::Ascending(_) => 0, Run::Equal(_) => 1 };
+        let mut bytes = vec![tag, self.cards().len() as u8];
+        bytes.extend(self.cards().iter().map(|c| c.to_id()));
+
+        bytes
+    }
+
+    /// Decodes a run from the format written by to_bytes(). Returns None if the
+    /// bytes aren't a well-formed encoding.
+    ///
+    /// ## Notes
+    /// The decoded run is not verified, so this should only be used on bytes
+    /// that were encoded from a valid run in the first place.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&tag, rest) = bytes.split_first()?;
+        let (&len, ids) = rest.split_first()?;
+        if ids.len() != len as usize { return None; }
+
+        let cards = ids.iter().map(|&id| Card::from_id(id)).collect::<Option<Vec<Card>>>()?;
+        match tag {
+            0 => Some(Run::Ascending(cards)),
+            1 => Some(Run::Equal(cards)),
+            _ => None
+        }
+    }
+
     /// Calculates the score that this run stands for, taking jokers into account.
+    ///
+    /// Every joker counts for the card it stands for according to the default
+    /// RunCoercionStrategy. An ace at the low end of an ascending run counts as
+    /// one, any other ace counts as eleven.
     pub fn get_score(&self) -> u8 {
-        unimplemented!("Calculating run score is unimplemented");
+        let cards = self.to_display_cards(&RunCoercionStrategy::default());
+        let low_ace = matches!(self, Run::Ascending(_)) && cards.len() > 1
+            && cards[0].card_type == CardType::Ace && cards[1].card_type == CardType::Number(2);
+
+        cards.iter().map(|c| c.score()).sum::<u8>() - if low_ace { 10 } else { 0 }
+    }
+
+    /// Calculates the highest score that this run can stand for, with every
+    /// joker standing for the highest card it legally can: jokers that don't
+    /// fill a gap extend an ascending run upwards for as long as they can (see
+    /// RunCoercionStrategy::highest_possible). A run with a single real card is
+    /// interpreted as the kind of run it is. Aces count like in get_score().
+    pub fn max_score(&self) -> u8 {
+        self.max_score_with(&ScoringRules::default())
+    }
+
+    /// Calculates the highest score that this run can stand for exactly like
+    /// max_score() does, but values the cards according to the given rules
+    /// (see Card::score_with()).
+    pub fn max_score_with(&self, rules: &ScoringRules) -> u8 {
+        let strategy = RunCoercionStrategy {
+            prefer_ascending: self.kind() == RunKind::Sequence,
+            highest_possible: true,
+            ..RunCoercionStrategy::default()
+        };
+        let cards = self.to_display_cards(&strategy);
+        let low_ace = cards.len() > 1 && cards[0].card_type == CardType::Ace && cards[1].card_type == CardType::Number(2);
+
+        cards.iter().map(|c| c.score_with(rules)).sum::<u8>() - if low_ace { 10 } else { 0 }
+    }
+
+    /// Compares two runs by their score (see get_score()), with the longer run
+    /// being the greater one when the scores are equal. Runs of equal score
+    /// and length are considered to be equal.
+    pub fn cmp_by_score(&self, other: &Run) -> Ordering {
+        self.get_score().cmp(&other.get_score())
+            .then(self.cards().len().cmp(&other.cards().len()))
     }
 
     /// Returns a new Run identical to `self`, but with all jokers replaced by
     /// the cards they actually stand for according to the given strategy.
     pub fn coerce_to_real(&self, strategy: RunCoercionStrategy) -> Run {
-        unimplemented!("Run coercion isn't yet implemented");
+        match self {
+            Run::Ascending(_) => Run::Ascending(self.to_display_cards(&strategy)),
+            Run::Equal(_)     => Run::Equal(self.to_display_cards(&strategy))
+        }
+    }
+
+    /// Returns the cards of this run in the order they should be displayed,
+    /// with all jokers replaced by the cards they stand for according to the
+    /// given strategy. Ascending runs are ordered by rank (with a high ace at
+    /// the end), equal runs by suit.
+    ///
+    /// Jokers in an ascending run first fill up the gaps between the real
+    /// cards, after which they extend the run upwards or downwards depending on
+    /// `highest_possible`. Jokers in an equal run take the missing suits in the
+    /// order of `suit_preference`. Whenever the run holds only a single real
+    /// card, `prefer_ascending` decides how to interpret it.
+    ///
+    /// ## Notes
+    /// The run is assumed to be valid. A run without any real cards is returned as-is.
+    pub fn to_display_cards(&self, strategy: &RunCoercionStrategy) -> Vec<Card> {
+        let (jokers, real): (Vec<Card>, Vec<Card>) = self.cards().iter()
+            .partition(|c| c.card_type == CardType::Joker);
+        if real.is_empty() { return self.cards().to_vec(); }
+
+        let ascending = if real.len() == 1 { strategy.prefer_ascending }
+                        else { matches!(self, Run::Ascending(_)) };
+
+        if !ascending {
+            let rank = real[0].card_type;
+            let mut cards = real.clone();
+            let mut missing: Vec<Suit> = Vec::new();
+            for &suit in strategy.suit_preference.iter().chain(RunCoercionStrategy::default().suit_preference.iter()) {
+                if !missing.contains(&suit) && real.iter().all(|c| c.suit != suit) { missing.push(suit); }
+            }
+            cards.extend(missing.into_iter().take(jokers.len()).map(|s| Card::new(rank, s)));
+            cards.sort_by_key(|c| c.suit);
+            return cards;
+        }
+
+        // an ace can be played both low and high, so pick whichever keeps the run tightest
+        let low: Vec<u8> = real.iter().map(|c| c.get_comparison_value()).collect();
+        let high: Vec<u8> = low.iter().map(|&p| if p == 1 { 14 } else { p }).collect();
+        let span = |p: &[u8]| p.iter().max().unwrap() - p.iter().min().unwrap();
+        let positions = if span(&high) < span(&low) { high } else { low };
+
+        let mut lowest = *positions.iter().min().unwrap();
+        let mut highest = *positions.iter().max().unwrap();
+        let gaps = (highest - lowest + 1) as usize - positions.len();
+
+        for _ in gaps..jokers.len() {
+            if strategy.highest_possible && highest < 14 { highest += 1; }
+            else if lowest > 1 { lowest -= 1; }
+            else if highest < 14 { highest += 1; }
+        }
+
+        (lowest..=highest).map(|p| Card::new(rank_at(p), real[0].suit)).collect()
     }
 }
 
 
 /// A function used to either construct a Run instance from the given cards,
-/// or return an Error if this isn't possible. This is the only way to directly
+/// or return a RunError if this isn't possible. This is the only way to directly
 /// create Runs.
-pub fn verify_run(mut cards: Vec<Card>) -> Result<Run, ()> {
-    assert!(cards.len() >= 3, "A run must consist of at least three cards.");
-    let mut dedupped = cards.clone(); dedupped.dedup();
-    assert!(dedupped == cards, "A run cannot contain duplicate cards.");
-
-    // The first check is trivial: checking if all card types are the same and all suits are different.
-    if cards.iter().all(|&c| c.card_type == cards[0].card_type) {
-        let mut suits_seen_so_far: Vec<Suit> = Vec::new();
-        for c in cards.iter() {
-            if suits_seen_so_far.contains(&c.suit) { break; }
-            else { suits_seen_so_far.push(c.suit); }
-        }
+///
+/// The run is classified by its real (non-joker) cards: if they all share a
+/// rank, it's an Equal run; if they all share a suit, it's an Ascending run.
+/// When there are fewer than two real cards, both are possible and the run is
+/// rejected as ambiguous. Real cards that share neither are rejected as mixed suits.
+///
+/// An ace in an Ascending run can be played either low (A-2-3) or high
+/// (Q-K-A), but never in between (K-A-2): a sequence doesn't wrap around.
+/// The same goes for a joker next to an ace: [JOKER, ♤A, ♤2] is accepted with
+/// the joker standing for ♤3 (it can't stand for ♤K), [JOKER, ♤2, ♤3] with the
+/// joker standing for either ♤A or ♤4, and [♤Q, ♤K, JOKER] with the joker
+/// standing for either ♤J or a high ♤A. A joker can never bridge the two ends,
+/// so [♤K, JOKER, ♤2] is rejected.
+///
+/// The cards of the returned run are sorted into a canonical order (by rank
+/// for Ascending runs, with a high ace after the king, and by suit for Equal
+/// runs, with jokers last), so runs made from the same cards compare equal
+/// regardless of their input order.
+pub fn verify_run(cards: Vec<Card>) -> Result<Run, RunError> {
+    verify_run_opts(cards, None)
+}
+
+/// Verifies the given cards exactly like verify_run() does, but if `decks` is
+/// given, first checks that the cards could have come out of a playing deck
+/// made of that many single decks (see validate_card_budget()), and rejects
+/// them with RunError::OverBudget otherwise. So three ♤A never make a run out
+/// of two decks, and neither do five jokers.
+pub fn verify_run_opts(mut cards: Vec<Card>, decks: Option<usize>) -> Result<Run, RunError> {
+    if let Some(decks) = decks {
+        validate_card_budget(&cards, decks).map_err(|over| RunError::OverBudget(over[0]))?;
+    }
+    if cards.len() < 3 { return Err(RunError::TooShort); }
 
-        return Ok(Run::Equal(cards))
+    let (jokers, mut real): (Vec<Card>, Vec<Card>) = cards.iter().partition(|c| c.card_type == CardType::Joker);
+    real.sort();
+    if let Some(pair) = real.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(RunError::DuplicateCard(pair[0]));
     }
+    if real.len() < 2 { return Err(RunError::Ambiguous); }
+
+    let same_rank = real.iter().all(|c| c.card_type == real[0].card_type);
+    let same_suit = real.iter().all(|c| c.suit == real[0].suit);
+
+    // Since there are no duplicates, at most one of these can hold.
+    if same_rank {
+        // There are only four suits, so jokers can't push an equal run past four cards.
+        if cards.len() > 4 { return Err(RunError::Invalid); }
+
+        cards.sort();
+        return Ok(Run::Equal(cards));
+    }
+
+    // Jokers don't have a suit of their own, so only the real cards are checked.
+    if !same_suit { return Err(RunError::MixedSuits); }
+
+    // However many jokers there are, a sequence can't go past either ace.
+    if cards.len() > MAX_SEQUENCE_LEN { return Err(RunError::TooLong); }
+
+    // Every gap in between the sorted real cards then has to be filled up by a
+    // joker. An ace can be played either low (A-2-3) or high (Q-K-A), but a
+    // sequence can never wrap around from king to two, so both are tried.
+    let low: Vec<u8> = real.iter().map(|c| c.get_comparison_value()).collect();
+    let mut high: Vec<u8> = low.iter().map(|&p| if p == 1 { 14 } else { p }).collect();
+    high.sort();
+    let gaps = |positions: &[u8]| positions.windows(2).map(|pair| usize::from(pair[1] - pair[0] - 1)).sum::<usize>();
+
+    let ace_high = gaps(&high) < gaps(&low);
+    if gaps(if ace_high { &high } else { &low }) > jokers.len() { return Err(RunError::Invalid); }
 
-    // If that check failed, the series of sorted cards may only have a maximum distance of one.
     cards.sort();
-    if cards.windows(2).fold(0u8, |acc, cards| acc + (cards[0].get_distance(&cards[1]))) > cards.len() as u8 {
-        return Err(());
+    if ace_high {
+        let ace = cards.remove(0);
+        cards.insert(real.len() - 1, ace);
+    }
+    Ok(Run::Ascending(cards))
+}
+
+/// Returns true if no real (non-joker) card appears in more than one of the
+/// given runs, i.e. if they could all be laid down at once without using the
+/// same card twice.
+///
+/// ## Notes
+/// A run doesn't tell which copy of a card it holds, so a card that appears in
+/// two runs is always taken to be the same one here, even though the full deck
+/// holds two copies of every card. To allow for the copies a hand actually
+/// holds, see GameConfig::validate_opening(). Jokers are all alike, so they're skipped.
+pub fn melds_are_disjoint(runs: &[Run]) -> bool {
+    shared_card(runs, &[]).is_none()
+}
+
+/// Finds the first real card that appears in more than one of the given runs,
+/// and more times in total than it appears in `held` (see melds_are_disjoint()).
+pub(crate) fn shared_card(runs: &[Run], held: &[Card]) -> Option<Card> {
+    let used = |card: &Card| runs.iter().flat_map(|r| r.cards()).filter(|&c| c == card).count();
+
+    runs.iter().enumerate().find_map(|(i, run)| {
+        run.cards().iter()
+            .filter(|c| c.card_type != CardType::Joker)
+            .filter(|c| runs[i + 1..].iter().any(|other| other.cards().contains(c)))
+            .find(|&c| used(c) > held.iter().filter(|&h| h == c).count())
+            .copied()
+    })
+}
+
+/// Returns every valid run of exactly `len` cards that can be formed out of
+/// the given pool, without duplicates.
+///
+/// ## Notes
+/// This tries every combination of `len` cards in the pool, so the pool should
+/// be about the size of a hand.
+pub fn enumerate_runs(pool: &[Card], len: usize) -> Vec<Run> {
+    let mut runs = Vec::new();
+    if len < 3 || len > pool.len() { return runs; }
+
+    let mut indices: Vec<usize> = (0..len).collect();
+    loop {
+        let mut cards: Vec<Card> = indices.iter().map(|&i| pool[i]).collect();
+        cards.sort();
+
+        // the same card can't appear twice in a single run, unless it's a joker
+        let duplicate = cards.windows(2).any(|pair| pair[0] == pair[1] && pair[0].card_type != CardType::Joker);
+        if !duplicate {
+            if let Ok(run) = verify_run(cards) {
+                if !runs.contains(&run) { runs.push(run); }
+            }
+        }
+
+        // advance to the next combination of indices, or stop if this was the last one
+        match (0..len).rev().find(|&i| indices[i] < pool.len() - len + i) {
+            Some(i) => {
+                indices[i] += 1;
+                for j in i + 1..len { indices[j] = indices[j - 1] + 1; }
+            },
+            None => break
+        }
     }
 
-    return Ok(Run::Ascending(cards));
+    runs
 }
@@ -0,0 +1,60 @@
+//! The strategy module contains the Strategy trait, which can be implemented
+//! to plug custom players into a Game, and the PlayerView struct that
+//! strategies base their decisions on.
+
+use crate::card::Card;
+use crate::hand::Hand;
+use crate::run::Run;
+use crate::table::DrawSource;
+
+/// Everything a player legally gets to see at the moment they have to make a decision.
+#[derive(Debug, Clone)]
+pub struct PlayerView<'a> {
+    /// The hand of the player that has to decide.
+    pub hand: &'a Hand,
+    /// All melds that lie on the table.
+    pub melds: &'a [Run],
+    /// The top card of the discard pile, if there is one.
+    pub discard_top: Option<Card>,
+    /// The amount of cards left in the stock.
+    pub stock_count: usize,
+    /// The amount of cards every player holds, indexed by player.
+    pub hand_sizes: Vec<usize>
+}
+
+/// A trait for everything that can make the decisions of a player.
+pub trait Strategy {
+    /// Decides where to draw a card from at the start of a turn.
+    fn choose_draw(&self, view: &PlayerView) -> DrawSource;
+
+    /// Decides which card to discard at the end of a turn. The returned card
+    /// has to be in `view.hand`.
+    fn choose_discard(&self, view: &PlayerView) -> Card;
+}
+
+/// A simple Strategy that only ever looks one card ahead.
+///
+/// It takes the top of the discard pile if that lets it form more melds than
+/// it currently can, and otherwise draws from the stock. It always discards
+/// the card suggested by Hand::suggest_discard().
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GreedyStrategy;
+
+impl Strategy for GreedyStrategy {
+    fn choose_draw(&self, view: &PlayerView) -> DrawSource {
+        if let Some(top) = view.discard_top {
+            let mut with_top = view.hand.clone();
+            with_top.0.push(top);
+
+            if with_top.best_partition().0.len() > view.hand.best_partition().0.len() {
+                return DrawSource::Discard;
+            }
+        }
+
+        DrawSource::Stock
+    }
+
+    fn choose_discard(&self, view: &PlayerView) -> Card {
+        view.hand.suggest_discard().expect("cannot discard from an empty hand")
+    }
+}
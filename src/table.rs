@@ -0,0 +1,243 @@
+//! The table module contains the Table and PublicTable structs and the
+//! DrawSource and TableError enums, i.e. everything that lies on the table in
+//! between the players.
+
+use crate::card::{Card, CardType};
+use crate::rules::ScoringRules;
+use crate::run::{Run, RunError};
+use crate::utils::{count_mismatches, generate_deck, validate_card_budget};
+
+/// An enum to represent the two places a player can draw a card from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DrawSource {
+    /// The face-down pile of cards that haven't been played yet.
+    Stock,
+    /// The face-up pile of cards that have been discarded.
+    Discard
+}
+
+/// A struct that represents everything on the table: the melds that have been
+/// laid down, the discard pile and the stock.
+///
+/// Both piles are stored with their top card at the end of the Vec.
+///
+/// If `freeze_on_joker` is set, discarding a joker freezes the discard pile:
+/// while `frozen` is set, nothing can be drawn from it. The pile thaws when
+/// it's turned over to replenish the stock (see replenish_stock()), unless the
+/// card that's left behind on top is a joker itself.
+#[derive(Debug, Clone, Default)]
+#[allow(missing_docs)]
+pub struct Table {
+    pub melds: Vec<Run>,
+    pub discard: Vec<Card>,
+    pub stock: Vec<Card>,
+    pub frozen: bool,
+    pub freeze_on_joker: bool
+}
+
+/// An enum to represent the ways a table can be inconsistent (see Table::validate()).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableError {
+    /// The meld at the given index isn't a valid run of its kind.
+    InvalidMeld {
+        /// The index of the meld on the table.
+        index: usize,
+        /// Why the meld isn't valid.
+        error: RunError
+    },
+    /// The table holds more copies of the contained card than the full
+    /// playing deck does, all of them in the same place (the melds, the
+    /// discard pile or the stock).
+    OverBudget(Card),
+    /// The table holds more copies of the contained card than the full
+    /// playing deck does, spread over more than one place, e.g. a card that
+    /// was laid down but never taken off the stock.
+    Overlap(Card)
+}
+
+/// What every player gets to see of the table (see Table::public_view()): the
+/// melds and the discard pile, but only the size of the stock, so the order of
+/// the stock can never leak out. With the serde feature, it can be serialized
+/// to send it to clients.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub struct PublicTable {
+    pub melds: Vec<Run>,
+    pub discard: Vec<Card>,
+    pub stock_count: usize,
+    pub frozen: bool
+}
+
+impl Table {
+    /// Returns the public part of the table, e.g. to send it to every client
+    /// in a multiplayer game.
+    pub fn public_view(&self) -> PublicTable {
+        PublicTable {
+            melds: self.melds.clone(),
+            discard: self.discard.clone(),
+            stock_count: self.stock.len(),
+            frozen: self.frozen
+        }
+    }
+
+    /// Returns the indices of every meld on the table that the given card could
+    /// be laid off onto (see Run::can_append()).
+    pub fn layoff_options(&self, card: &Card) -> Vec<usize> {
+        self.melds.iter().enumerate()
+            .filter(|(_, meld)| meld.can_append(card))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Finds the melds on the table that claim more copies of a card than the
+    /// full playing deck holds (see validate_card_budget()). Returns the index
+    /// pairs (lowest first) of every two melds that both hold such a card, in
+    /// ascending order.
+    pub fn duplicate_melds(&self) -> Vec<(usize, usize)> {
+        let cards: Vec<Card> = self.melds.iter().flat_map(|m| m.cards().iter().copied()).collect();
+        let over = match validate_card_budget(&cards, 2) {
+            Ok(())    => return Vec::new(),
+            Err(over) => over
+        };
+
+        let mut pairs = Vec::new();
+        for i in 0..self.melds.len() {
+            for j in i + 1..self.melds.len() {
+                let shared = over.iter().any(|c| self.melds[i].cards().contains(c) && self.melds[j].cards().contains(c));
+                if shared { pairs.push((i, j)); }
+            }
+        }
+
+        pairs
+    }
+
+    /// Checks every invariant of the table at once: every meld has to be a
+    /// valid run of its kind (see Run::from_cards()), and the melds, the
+    /// discard pile and the stock together may not hold more copies of any card
+    /// than the full playing deck of two single decks (see validate_card_budget()).
+    /// Returns every problem that was found, the melds first.
+    pub fn validate(&self) -> Result<(), Vec<TableError>> {
+        self.validate_against(&generate_deck(false))
+    }
+
+    /// Checks every invariant of the table exactly like validate() does, but
+    /// against the given deck rather than two single decks, e.g. the one a
+    /// game was dealt from: no card may appear more often than it does in there.
+    pub fn validate_against(&self, deck: &[Card]) -> Result<(), Vec<TableError>> {
+        let mut errors: Vec<TableError> = self.melds.iter().enumerate()
+            .filter_map(|(index, meld)| Run::from_cards(meld.kind(), meld.cards().to_vec()).err().map(|error| TableError::InvalidMeld { index, error }))
+            .collect();
+
+        let melded: Vec<Card> = self.melds.iter().flat_map(|m| m.cards().iter().copied()).collect();
+        let parts = [&melded[..], &self.discard[..], &self.stock[..]];
+        let over = count_mismatches(deck, &parts).into_iter().filter(|&(_, n)| n < 0).map(|(card, _)| card);
+        errors.extend(over.map(|card| {
+            let places = parts.iter().filter(|part| part.contains(&card)).count();
+            if places > 1 { TableError::Overlap(card) } else { TableError::OverBudget(card) }
+        }));
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Takes the top card off of the stock, if there is one.
+    pub fn draw_from_stock(&mut self) -> Option<Card> {
+        self.stock.pop()
+    }
+
+    /// Takes the top card off of the discard pile, if there is one and the
+    /// pile isn't frozen. This is the only way cards leave the discard pile
+    /// during play, so when several players try to take the same card, whoever
+    /// gets here first gets it and everyone after them gets None.
+    pub fn draw_from_discard(&mut self) -> Option<Card> {
+        if self.frozen { return None; }
+        self.discard.pop()
+    }
+
+    /// Looks at the top card of the discard pile without taking it.
+    pub fn peek_discard(&self) -> Option<&Card> {
+        self.discard.last()
+    }
+
+    /// Returns the cards in the discard pile that are buried, bottom first.
+    ///
+    /// Only the top card of the discard pile can ever be taken, so every card
+    /// below it is buried: it's out of play unless the stock runs out and the
+    /// discard pile is turned over to replenish it (see replenish_stock()).
+    pub fn buried_cards(&self) -> Vec<Card> {
+        match self.discard.split_last() {
+            Some((_, buried)) => buried.to_vec(),
+            None => Vec::new()
+        }
+    }
+
+    /// Renders the discard pile in card notation (see Card::to_notation()),
+    /// bottom to top, separated by spaces, e.g. "S2 HX J".
+    pub fn discard_notation(&self) -> String {
+        self.discard.iter().map(|c| c.to_notation()).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Counts the jokers that everyone can see: the ones in the melds and the
+    /// ones in the discard pile (buried or not).
+    pub fn visible_jokers(&self) -> usize {
+        self.melds.iter().flat_map(|m| m.cards())
+            .chain(self.discard.iter())
+            .filter(|c| c.card_type == CardType::Joker)
+            .count()
+    }
+
+    /// Run-length encodes the discard pile, bottom to top, for compact logging:
+    /// every stretch of copies of the same card becomes a single pair of the
+    /// card and how many times it's repeated. Stretches of more than 255
+    /// copies are split up. See expand_discard_rle() for the reverse.
+    pub fn discard_rle(&self) -> Vec<(Card, u8)> {
+        let mut runs: Vec<(Card, u8)> = Vec::new();
+        for &card in self.discard.iter() {
+            match runs.last_mut() {
+                Some((last, n)) if *last == card && *n < u8::MAX => *n += 1,
+                _ => runs.push((card, 1))
+            }
+        }
+
+        runs
+    }
+
+    /// Expands a discard pile that was encoded by discard_rle() back into
+    /// its cards, bottom to top.
+    pub fn expand_discard_rle(runs: &[(Card, u8)]) -> Vec<Card> {
+        runs.iter().flat_map(|&(card, n)| std::iter::repeat_n(card, n.into())).collect()
+    }
+
+    /// Returns the amount of cards in the discard pile.
+    pub fn discard_len(&self) -> usize {
+        self.discard.len()
+    }
+
+    /// Sums up the score of every card in the discard pile according to the
+    /// given rules (see Card::score_with()), i.e. how many points have been
+    /// thrown away so far.
+    pub fn discard_value(&self, rules: &ScoringRules) -> u16 {
+        self.discard.iter().map(|c| u16::from(c.score_with(rules))).sum()
+    }
+
+    /// Refills an empty stock by turning over the discard pile, save for its
+    /// top card, which stays behind to start the new discard pile. Returns
+    /// true if any cards were moved.
+    pub fn replenish_stock(&mut self) -> bool {
+        if !self.stock.is_empty() || self.discard.len() <= 1 { return false; }
+
+        let top = self.discard.pop().unwrap();
+        self.stock = self.discard.drain(..).rev().collect();
+        self.discard.push(top);
+        self.frozen = self.freeze_on_joker && top.card_type == CardType::Joker;
+
+        true
+    }
+
+    /// Puts a card on top of the discard pile, freezing it if the card is a
+    /// joker and `freeze_on_joker` is set.
+    pub fn discard(&mut self, card: Card) {
+        if self.freeze_on_joker && card.card_type == CardType::Joker { self.frozen = true; }
+        self.discard.push(card);
+    }
+}
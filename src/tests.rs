@@ -1,12 +1,78 @@
 #![allow(missing_docs)]
 
 #![cfg(test)]
-use crate::{card::*, hand::*, utils::*, run::*};
+use std::cmp::Ordering;
+use crate::{SINGLE_DECK_SIZE, FULL_DECK_SIZE, DEFAULT_JOKER_SCORE, OPENING_SCORE};
+use crate::{card::*, hand::*, utils::*, run::*, rules::*, strategy::*, game::*, table::*};
+
+/// Parses a card from its notation, which has to be valid (see Card::try_from()).
+fn card(notation: &str) -> Card {
+    Card::try_from(notation).unwrap()
+}
+
+/// Parses every card in the given notation (see card()).
+fn cards(notation: &[&str]) -> Vec<Card> {
+    notation.iter().map(|s| card(s)).collect()
+}
+
+/// Replaces the given card wherever it is in the game (the stock, the discard
+/// pile or any hand but the skipped player's) by the replacement, or takes it
+/// out if there is none. Returns false if the card isn't anywhere.
+fn swap_out(game: &mut Game, skip: usize, wanted: Card, replacement: Option<Card>) -> bool {
+    let mut places = vec![&mut game.table.stock, &mut game.table.discard];
+    places.extend(game.players.iter_mut().enumerate().filter(|&(p, _)| p != skip).map(|(_, player)| &mut player.hand.0));
+
+    for place in places {
+        if let Some(i) = place.iter().position(|&c| c == wanted) {
+            match replacement {
+                Some(r) => place[i] = r,
+                None => { place.remove(i); }
+            }
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Gives the player a hand of the given cards, each taken from wherever it is
+/// in the game, with the player's own cards going in its place, so the game
+/// still holds the deck it was dealt from. Own cards that are left over go to
+/// the bottom of the stock.
+fn rig_hand(game: &mut Game, player: usize, hand: &[Card]) {
+    let mut spare = std::mem::take(&mut game.players[player].hand.0);
+    for &wanted in hand {
+        match spare.iter().position(|&c| c == wanted) {
+            Some(i) => { spare.remove(i); },
+            None => {
+                let replacement = spare.pop();
+                assert!(swap_out(game, player, wanted, replacement), "{:?} isn't left in the game", wanted);
+            }
+        }
+    }
+
+    game.table.stock.splice(0..0, spare);
+    game.players[player].hand = Hand(hand.to_vec());
+}
+
+/// Puts the given card on top of the stock or the discard pile, taken from
+/// wherever it is in the game, with the former top card going in its place
+/// (see rig_hand()).
+fn rig_top(game: &mut Game, source: DrawSource, notation: &str) {
+    let wanted = card(notation);
+    let pile = match source { DrawSource::Stock => &mut game.table.stock, DrawSource::Discard => &mut game.table.discard };
+    let top = pile.pop().unwrap();
+    if top != wanted {
+        assert!(swap_out(game, usize::MAX, wanted, Some(top)), "{:?} isn't left in the game", wanted);
+    }
+
+    match source { DrawSource::Stock => &mut game.table.stock, DrawSource::Discard => &mut game.table.discard }.push(wanted);
+}
 
 #[test]
 fn get_deck() {
     let deck = generate_single_deck(false);
-    assert_eq!(deck.len(), 54);
+    assert_eq!(deck.len(), SINGLE_DECK_SIZE);
 }
 
 #[test]
@@ -21,13 +87,37 @@ fn shuffled_deck() {
 #[test]
 fn full_deck() {
     let deck = generate_deck(false);
-    assert_eq!(deck.len(), 108);
+    assert_eq!(deck.len(), FULL_DECK_SIZE);
 }
 
 #[test]
 fn generate_hand() {
-    let mut deck = generate_deck(true);
-    let _hand = Hand::draw(&mut deck);
+    let original = generate_deck(true);
+    let mut deck = original.clone();
+    let hand = Hand::draw(&mut deck);
+    assert_deck_conservation(&original, &[&hand.0, &deck]);
+}
+
+#[test]
+fn deal_game() {
+    let original = generate_deck(true);
+    let game = Game::deal(original.clone(), 4).unwrap();
+
+    let mut parts: Vec<&[Card]> = game.players.iter().map(|p| p.hand.0.as_slice()).collect();
+    parts.push(&game.table.discard);
+    parts.push(&game.table.stock);
+    assert_deck_conservation(&original, &parts);
+}
+
+#[test]
+#[should_panic(expected = "not conserved")]
+fn deck_conservation_catches_lost_card() {
+    let original = generate_deck(true);
+    let mut deck = original.clone();
+    let hand = Hand::draw(&mut deck);
+    deck.pop();
+
+    assert_deck_conservation(&original, &[&hand.0, &deck]);
 }
 
 #[test]
@@ -35,13 +125,13 @@ fn hand_score() {
     let hand0 = Hand(vec![]);
     assert_eq!(hand0.score(), 0);
 
-    let hand1 = Hand(vec![Card::from("SA")]);
+    let hand1 = Hand(vec![card("SA")]);
     assert_eq!(hand1.score(), 1);
 
-    let hand2 = Hand(vec![Card::from("SK"), Card::from("HQ"), Card::from("DJ")]);
+    let hand2 = Hand(vec![card("SK"), card("HQ"), card("DJ")]);
     assert_eq!(hand2.score(), 30);
 
-    let hand3 = Hand(vec![Card::from("S8"), Card::from("S9"), Card::from("J")]);
+    let hand3 = Hand(vec![card("S8"), card("S9"), card("J")]);
     assert_eq!(hand3.score(), 42);
 }
 
@@ -52,17 +142,1959 @@ fn verify_runs() {
     // assert_eq!(verify_run(test_cards1.clone()),
     //            Ok(Run::Ascending(test_cards1.clone())));
 
-    let mut test_cards2 = vec!["S2", "S3", "SA"].iter().map(|s| Card::from(*s)).collect::<Vec<_>>();
+    let mut test_cards2 = cards(&["S2", "S3", "SA"]);
     test_cards2.sort();
     assert_eq!(verify_run(test_cards2.clone()),
                 Ok(Run::Ascending(test_cards2.clone())));
 
-    let mut test_cards3 = vec!["S2", "D2", "H2"].iter().map(|s| Card::from(*s)).collect::<Vec<_>>();
-    test_cards3.sort();
+    let test_cards3 = cards(&["H2", "S2", "D2"]);
     assert_eq!(verify_run(test_cards3.clone()),
-                Ok(Run::Equal(test_cards3.clone())));
+                Ok(Run::Equal(cards(&["S2", "D2", "H2"]))));
 
-    let test_cards4 = vec!["S2", "D2", "H3"].iter().map(|s| Card::from(*s)).collect::<Vec<_>>();
+    let test_cards4 = cards(&["S2", "D2", "H3"]);
     assert_eq!(verify_run(test_cards4.clone()),
-                Err(()));
+                Err(RunError::MixedSuits));
+}
+
+#[test]
+fn hand_ranking() {
+    let rules = ScoringRules::default();
+
+    // both hands are worth 19 points, but only the first one holds a meld
+    let melded = Hand(cards(&["S2", "S3", "S4", "HK"]));
+    let scattered = Hand(cards(&["S2", "D3", "H4", "CK"]));
+    assert_eq!(melded.score_with(&rules), scattered.score_with(&rules));
+
+    assert_eq!(melded.cmp_for_ranking(&scattered, &rules), Ordering::Less);
+    assert_eq!(scattered.cmp_for_ranking(&melded, &rules), Ordering::Greater);
+    assert_eq!(melded.cmp_for_ranking(&melded.clone(), &rules), Ordering::Equal);
+
+    let lower = Hand(cards(&["S2", "D3", "H4", "C9"]));
+    assert_eq!(lower.cmp_for_ranking(&melded, &rules), Ordering::Less);
+}
+
+#[test]
+fn greedy_game() {
+    let mut game = Game::deal(generate_deck(true), 2).unwrap();
+    let winner = game.play(&[&GreedyStrategy, &GreedyStrategy]);
+
+    match winner {
+        Some(player) => assert!(game.players[player].hand.0.is_empty()),
+        None         => assert!(game.table.stock.is_empty())
+    }
+}
+
+#[test]
+fn display_coerced_runs() {
+    let low = RunCoercionStrategy::default();
+    let high = RunCoercionStrategy { highest_possible: true, ..low };
+
+    let run = Run::Ascending(cards(&["S3", "S4", "J"]));
+    assert_eq!(run.to_display_cards(&low), cards(&["S2", "S3", "S4"]));
+    assert_eq!(run.to_display_cards(&high), cards(&["S3", "S4", "S5"]));
+    assert_eq!(run.coerce_to_real(high), Run::Ascending(cards(&["S3", "S4", "S5"])));
+
+    let gapped = Run::Ascending(cards(&["HQ", "HA", "J"]));
+    assert_eq!(gapped.to_display_cards(&low), cards(&["HQ", "HK", "HA"]));
+
+    let equal = Run::Equal(cards(&["J", "CQ", "HQ"]));
+    let clubs_first = RunCoercionStrategy { suit_preference: [Suit::Clubs, Suit::Diamonds, Suit::Spades, Suit::Hearts], ..low };
+    assert_eq!(equal.to_display_cards(&clubs_first), cards(&["DQ", "CQ", "HQ"]));
+}
+
+#[test]
+fn verify_long_and_joker_runs() {
+    let long = cards(&["SA", "S2", "S3", "S4", "S5", "S6", "S7", "S8", "S9", "SX", "SJ", "SQ", "SK"]);
+    assert!(matches!(verify_run(long), Ok(Run::Ascending(_))));
+
+    assert!(verify_run(cards(&["S2", "S3", "S5"])).is_err());
+    assert!(verify_run(cards(&["S2", "J", "S4"])).is_ok());
+    assert!(verify_run(cards(&["SQ", "SK", "J", "J"])).is_ok());
+    assert!(verify_run(cards(&["S2", "J", "S5"])).is_err());
+    assert!(verify_run(cards(&["SA", "S2", "S4", "S9", "J"])).is_err());
+}
+
+#[test]
+fn enumerate_length_three_runs() {
+    let pool = cards(&["S2", "S3", "S4", "S5", "D2", "H2", "S2"]);
+
+    let runs = enumerate_runs(&pool, 3);
+    assert_eq!(runs.len(), 3);
+    assert!(runs.contains(&Run::Ascending(cards(&["S2", "S3", "S4"]))));
+    assert!(runs.contains(&Run::Ascending(cards(&["S3", "S4", "S5"]))));
+    assert!(runs.contains(&Run::Equal(cards(&["S2", "D2", "H2"]))));
+
+    assert!(enumerate_runs(&pool, 2).is_empty());
+    assert_eq!(enumerate_runs(&pool, 4).len(), 1);
+}
+
+#[test]
+fn configured_jokers() {
+    let config = GameConfig { jokers_per_deck: 4, scoring: ScoringRules { joker_value: 50, ..ScoringRules::default() }, ..GameConfig::default() };
+
+    let deck = generate_single_deck_with(false, &config);
+    assert_eq!(deck.len(), 56);
+    assert_eq!(deck.iter().filter(|c| c.card_type == CardType::Joker).count(), 4);
+    assert_eq!(generate_deck_with(false, &config).len(), 112);
+
+    let hand = Hand(vec![card("J"), card("S5")]);
+    assert_eq!(card("J").score_with(&config.scoring), 50);
+    assert_eq!(hand.score_with(&config.scoring), 55);
+    assert_eq!(hand.score_with(&ScoringRules::default()), u16::from(DEFAULT_JOKER_SCORE) + 5);
+}
+
+#[test]
+fn closing_discards() {
+    let closing = Hand(cards(&["S2", "S3", "S4", "D7", "D8", "D9", "HK"]));
+    assert_eq!(closing.closing_discards(), cards(&["HK"]));
+
+    let stuck = Hand(cards(&["S2", "S3", "S4", "D7", "HK"]));
+    assert!(stuck.closing_discards().is_empty());
+}
+
+#[test]
+fn can_go_out_beyond_best_partition() {
+    // the best partition melds J-SA-HA and keeps S3 and S4, but SA-J-S3-S4 melds with HA to discard
+    let hand = Hand(cards(&["J", "SA", "S4", "S3", "HA"]));
+    assert_eq!(hand.best_partition().1.len(), 2);
+    assert!(hand.can_go_out());
+    assert_eq!(hand.closing_discards(), cards(&["HA"]));
+}
+
+#[test]
+fn binary_round_trip() {
+    for card in generate_single_deck(false) {
+        assert_eq!(Card::from_bytes(&card.to_bytes()), Some(card));
+    }
+
+    let hand = Hand(cards(&["SA", "HK", "DX", "C7", "J"]));
+    let bytes = hand.to_bytes();
+    assert_eq!(bytes.len(), 6);
+    assert_eq!(Hand::from_bytes(&bytes).unwrap().0, hand.0);
+
+    let ascending = Run::Ascending(cards(&["S2", "S3", "J"]));
+    assert_eq!(Run::from_bytes(&ascending.to_bytes()), Some(ascending));
+
+    let equal = Run::Equal(cards(&["SQ", "DQ", "HQ"]));
+    assert_eq!(Run::from_bytes(&equal.to_bytes()), Some(equal));
+
+    assert_eq!(Run::from_bytes(&[2, 0]), None);
+    assert_eq!(Hand::from_bytes(&[3, 0, 1]).map(|h| h.0), None);
+}
+
+#[test]
+fn sort_runs_by_score() {
+    let low = Run::Ascending(cards(&["SA", "S2", "S3"]));
+    let high = Run::Ascending(cards(&["HQ", "HK", "HA"]));
+    let joker = Run::Ascending(cards(&["D7", "J", "D9"]));
+    let set = Run::Equal(cards(&["S7", "D7", "H7"]));
+    assert_eq!(low.get_score(), 6);
+    assert_eq!(high.get_score(), 31);
+    assert_eq!(joker.get_score(), 24);
+
+    let mut runs = vec![low.clone(), set.clone(), high.clone(), joker.clone()];
+    runs.sort_by(|a, b| b.cmp_by_score(a));
+    assert_eq!(runs, vec![high, joker, set, low]);
+}
+
+#[test]
+fn peek_discard() {
+    let mut table = Table::default();
+    assert_eq!(table.peek_discard(), None);
+    assert_eq!(table.discard_len(), 0);
+
+    table.discard(card("S5"));
+    table.discard(card("HK"));
+    assert_eq!(table.peek_discard(), Some(&card("HK")));
+    assert_eq!(table.discard_len(), 2);
+}
+
+#[test]
+fn parse_cards() {
+    assert_eq!("".parse::<Card>(), Err(CardParseError::EmptyString));
+    assert_eq!("SKX".parse::<Card>(), Err(CardParseError::TooLong));
+    assert_eq!("X2".parse::<Card>(), Err(CardParseError::InvalidSuit('X')));
+    assert_eq!("S1".parse::<Card>(), Err(CardParseError::InvalidType('1')));
+    assert_eq!("HX".parse::<Card>(), Ok(Card::new(CardType::Number(10), Suit::Hearts)));
+    assert_eq!("J".parse::<Card>(), Ok(Card::new(CardType::Joker, Suit::JokerSuit)));
+
+    assert_eq!(Card::try_from(""), Err(CardParseError::EmptyString));
+    assert_eq!(Card::try_from("S1"), Err(CardParseError::InvalidType('1')));
+    assert_eq!(Card::try_from("HX"), Ok(Card::new(CardType::Number(10), Suit::Hearts)));
+}
+
+#[test]
+fn set_up_new_game() {
+    let config = GameConfig::default();
+    let game = new_game(4, &config, 42).unwrap();
+
+    assert_eq!(game.players.len(), 4);
+    assert!(game.players.iter().all(|p| p.hand.0.len() == 13));
+    assert_eq!(game.table.discard_len(), 1);
+    assert_eq!(game.table.stock.len(), FULL_DECK_SIZE - 4 * 13 - 1);
+
+    let same = new_game(4, &config, 42).unwrap();
+    assert_eq!(same.table.stock, game.table.stock);
+    assert_ne!(new_game(4, &config, 43).unwrap().table.stock, game.table.stock);
+}
+
+#[test]
+fn card_accessors() {
+    let card = card("DQ");
+    assert_eq!(card.suit(), Suit::Diamonds);
+    assert_eq!(card.rank(), CardType::Queen);
+}
+
+#[test]
+fn classify_runs() {
+    assert_eq!(verify_run(cards(&["S2", "S2", "D2"])), Err(RunError::DuplicateCard(card("S2"))));
+    assert_eq!(verify_run(cards(&["S2", "D2", "S3"])), Err(RunError::MixedSuits));
+    assert_eq!(verify_run(cards(&["S2", "D2"])), Err(RunError::TooShort));
+    assert_eq!(verify_run(cards(&["SK", "J", "J"])), Err(RunError::Ambiguous));
+    assert_eq!(verify_run(cards(&["J", "CQ", "HQ"])), Ok(Run::Equal(cards(&["CQ", "HQ", "J"]))));
+    assert_eq!(verify_run(cards(&["J", "SQ", "CQ", "DQ", "HQ"])), Err(RunError::Invalid));
+}
+
+#[test]
+fn rotate_dealer() {
+    let mut game = new_game(3, &GameConfig::default(), 7).unwrap();
+    assert_eq!((game.dealer, game.current_player), (0, 1));
+
+    for (dealer, first) in [(1, 2), (2, 0), (0, 1), (1, 2)] {
+        game.rotate_dealer();
+        assert_eq!((game.dealer, game.current_player), (dealer, first));
+    }
+}
+
+#[test]
+fn minimum_deck_size() {
+    assert_eq!(min_deck_for(2, 13), 28);
+
+    let deck = generate_deck(false);
+    let game = Game::deal(deck[..28].to_vec(), 2).unwrap();
+    assert_eq!(game.table.stock.len(), 1);
+
+    assert_eq!(Game::deal(deck[..27].to_vec(), 2).err(), Some(SetupError::DeckTooSmall { needed: 28, available: 27 }));
+    assert_eq!(Game::deal(deck.clone(), 0).err(), Some(SetupError::NoPlayers));
+    assert!(new_game(8, &GameConfig::default(), 0).is_ok());
+    assert!(new_game(9, &GameConfig::default(), 0).is_err());
+}
+
+#[test]
+fn sets_and_sequences() {
+    let sequence = verify_run(cards(&["H4", "H5", "H6"])).unwrap();
+    assert!(sequence.is_sequence() && !sequence.is_set());
+
+    let set = verify_run(cards(&["H4", "S4", "C4"])).unwrap();
+    assert!(set.is_set() && !set.is_sequence());
+}
+
+#[test]
+fn parse_random_strings() {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    // a mix of valid notation, unicode suits and arbitrary (multibyte) characters
+    let pool = ['S', 'H', 'D', 'C', 'J', 'A', 'K', 'Q', 'X', '2', '9', '?', '♠', '♥', 'é', '\0'];
+    let mut rng = StdRng::seed_from_u64(120);
+
+    for _ in 0..10_000 {
+        let len = rng.gen_range(0..5);
+        let notation: String = (0..len)
+            .map(|_| if rng.gen_bool(0.8) { pool[rng.gen_range(0..pool.len())] } else { rng.gen::<char>() })
+            .collect();
+
+        assert_eq!(Card::try_from(notation.as_str()), notation.parse::<Card>());
+        if notation.parse::<Card>().is_ok() {
+            assert!((1..=2).contains(&notation.chars().count()), "{:?} parsed into a card", notation);
+        }
+    }
+}
+
+#[test]
+fn parse_unicode_suits() {
+    assert_eq!("♠A".parse::<Card>(), Ok(Card::new(CardType::Ace, Suit::Spades)));
+    assert_eq!("♡X".parse::<Card>(), Ok(Card::new(CardType::Number(10), Suit::Hearts)));
+    assert_eq!("🃏".parse::<Card>(), Ok(Card::new(CardType::Joker, Suit::JokerSuit)));
+    assert_eq!(card("S7").to_unicode(), "♠7");
+
+    for card in generate_single_deck(false) {
+        assert_eq!(card.to_unicode().parse::<Card>(), Ok(card));
+    }
+}
+
+#[test]
+fn evaluate_candidates() {
+    let rules = ScoringRules::default();
+    let hand = Hand(cards(&["S5", "S6", "HK", "D2"]));
+
+    // the seven of spades completes a run, the nine of clubs is just more deadwood
+    assert_eq!(hand.evaluate_with(card("S7"), &rules), 18 - 12);
+    assert_eq!(hand.evaluate_with(card("C9"), &rules), -32);
+    assert!(hand.evaluate_with(card("S7"), &rules) > hand.evaluate_with(card("C9"), &rules));
+
+    // a joker worth less than a king is better left over than melded in place of one
+    let kings = Hand(cards(&["HK", "DK", "CK", "SK"]));
+    assert_eq!(kings.evaluate_with(card("J"), &rules), 40 - 10);
+    assert_eq!(kings.evaluate_with(card("J"), &ScoringRules { joker_value: 5, ..rules }), 40 - 5);
+}
+
+#[test]
+fn score_forced_round() {
+    let mut game = Game::deal(generate_single_deck(false), 2).unwrap();
+    game.config.scoring.winner_bonus = 10;
+    game.current_player = 0;
+    rig_hand(&mut game, 0, &cards(&["SJ", "SQ", "SK", "D7", "D8", "HK"]));
+    game.players[0].score = 30;
+    rig_hand(&mut game, 1, &cards(&["CK", "HQ", "D5"]));
+    rig_top(&mut game, DrawSource::Discard, "C2");
+    rig_top(&mut game, DrawSource::Stock, "D9");
+
+    assert!(game.play_turn(&GreedyStrategy));
+    assert!(game.players[0].hand.0.is_empty());
+
+    game.score_round();
+    assert_eq!(game.players[0].score, 20);
+    assert_eq!(game.players[1].score, 25);
+}
+
+#[test]
+fn play_turn_follows_the_rules_for_going_out() {
+    let scripted = |hand: &[&str], draw: &str, config: GameConfig| {
+        let mut game = Game::from_deck(generate_single_deck(false), 2, &config).unwrap();
+        game.current_player = 0;
+        rig_hand(&mut game, 0, &cards(hand));
+        rig_top(&mut game, DrawSource::Discard, "C2");
+        rig_top(&mut game, DrawSource::Stock, draw);
+        let out = game.play_turn(&GreedyStrategy);
+        assert_eq!(game.events[0], GameEvent::Draw { player: 0, source: DrawSource::Stock, card: card(draw) });
+
+        (out, game)
+    };
+
+    // melding everything but HK would only be worth 33 points, too few to open with
+    let (out, game) = scripted(&["S2", "S3", "S4", "D7", "D8", "HK"], "D9", GameConfig::default());
+    assert!(!out);
+    assert!(game.table.melds.is_empty());
+    assert_eq!(game.players[0].hand.0.len(), 6);
+
+    // a hand that melds completely still needs a card to discard, unless the config says otherwise
+    let hand = ["SJ", "SQ", "SK", "DJ", "DQ"];
+    let (out, game) = scripted(&hand, "DK", GameConfig::default());
+    assert!(!out);
+    assert!(game.table.melds.is_empty());
+
+    let (out, game) = scripted(&hand, "DK", GameConfig { discard_to_go_out: false, ..GameConfig::default() });
+    assert!(out);
+    assert!(game.players[0].hand.0.is_empty());
+    assert_eq!(game.table.melds.len(), 2);
+    assert!(!game.events.iter().any(|e| matches!(e, GameEvent::Discard { .. })));
+}
+
+#[test]
+fn detect_stalemate() {
+    let mut game = Game::deal(generate_deck(false), 2).unwrap();
+    game.table.stock.clear();
+    game.table.discard = cards(&["S2"]);
+    assert!(game.is_stalemate());
+    assert!(game.play_turn(&GreedyStrategy));
+    assert_eq!(game.play(&[&GreedyStrategy, &GreedyStrategy]), None);
+
+    // with more cards on the discard pile, the stock can be replenished
+    game.table.discard = cards(&["S2", "H7", "DK"]);
+    assert!(!game.is_stalemate());
+    assert!(game.table.replenish_stock());
+    assert_eq!(game.table.stock, cards(&["H7", "S2"]));
+    assert_eq!(game.table.discard, cards(&["DK"]));
+}
+
+#[test]
+fn spectator_snapshot() {
+    let mut game = new_game(3, &GameConfig::default(), 125).unwrap();
+    game.table.melds.push(verify_run(cards(&["H4", "H5", "H6"])).unwrap());
+    game.players[2].melds.push(0);
+    game.players[1].hand.0.truncate(10);
+
+    let snapshot = game.snapshot();
+    assert_eq!(snapshot.players.iter().map(|p| p.hand_size).collect::<Vec<_>>(), vec![13, 10, 13]);
+    assert_eq!(snapshot.players[2].melds, game.table.melds);
+    assert!(snapshot.players[0].melds.is_empty());
+    assert_eq!(snapshot.discard_top, game.table.peek_discard().copied());
+    assert_eq!(snapshot.stock_size, game.table.stock.len());
+    assert_eq!(snapshot.current_player, 1);
+
+    // the snapshot only carries counts, so it stays the same whatever the hands hold
+    let hand = game.players[0].hand.clone();
+    game.players[0].hand = game.players[2].hand.clone();
+    game.players[2].hand = hand;
+    assert_eq!(game.snapshot(), snapshot);
+}
+
+#[test]
+fn layoff_options() {
+    let mut table = Table::default();
+    for meld in [&["S2", "S3", "S4"], &["D5", "H5", "C5"], &["HJ", "HQ", "HK"], &["S6", "S7", "S8"]] {
+        table.melds.push(verify_run(cards(meld)).unwrap());
+    }
+
+    assert_eq!(table.layoff_options(&card("S5")), vec![0, 1, 3]);
+    assert_eq!(table.layoff_options(&card("HX")), vec![2]);
+    assert!(table.layoff_options(&card("D9")).is_empty());
+    assert!(table.layoff_options(&card("S3")).is_empty());
+}
+
+#[test]
+fn swap_jokers() {
+    let mut run = verify_run(cards(&["D4", "J", "D6", "D7"])).unwrap();
+    assert_eq!(run.resolve_jokers(), vec![(3, card("D5"))]);
+    assert_eq!(run.swappable_joker(&card("D5")), Some(3));
+    assert_eq!(run.swappable_joker(&card("D8")), None);
+    assert_eq!(run.swappable_joker(&card("H5")), None);
+
+    assert_eq!(run.swap_joker(0, card("D5")), Err(RunError::NotAJoker));
+    assert_eq!(run.swap_joker(9, card("D5")), Err(RunError::NotAJoker));
+    assert_eq!(run.swap_joker(3, card("D9")), Err(RunError::Invalid));
+    assert_eq!(run.swap_joker(3, card("D5")), Ok(card("J")));
+    assert_eq!(run, Run::Ascending(cards(&["D4", "D5", "D6", "D7"])));
+}
+
+#[test]
+fn all_partitions() {
+    let hand = Hand(cards(&["S5", "S6", "S7", "D7", "H7"]));
+
+    let partitions = hand.all_partitions();
+    assert_eq!(partitions.len(), 3);
+    assert!(partitions.contains(&(vec![], hand.0.clone())));
+    assert!(partitions.contains(&(vec![Run::Ascending(cards(&["S5", "S6", "S7"]))], cards(&["D7", "H7"]))));
+    assert!(partitions.contains(&(vec![Run::Equal(cards(&["S7", "D7", "H7"]))], cards(&["S5", "S6"]))));
+
+    // the set leaves less in deadwood than the sequence does
+    assert_eq!(hand.best_partition(), (vec![Run::Equal(cards(&["S7", "D7", "H7"]))], cards(&["S5", "S6"])));
+}
+
+#[test]
+fn coercion_tie_breaks() {
+    let set = Run::Equal(cards(&["J", "SQ", "HQ"]));
+
+    // both fits score the same, so the suit preference decides
+    let clubs_first = RunCoercionStrategy {
+        highest_possible: true,
+        suit_preference: [Suit::Clubs, Suit::Diamonds, Suit::Spades, Suit::Hearts],
+        ..RunCoercionStrategy::default()
+    };
+    let diamonds_first = RunCoercionStrategy { suit_preference: [Suit::Diamonds, Suit::Clubs, Suit::Spades, Suit::Hearts], ..clubs_first };
+
+    let clubs = set.coerce_to_real(clubs_first);
+    let diamonds = set.coerce_to_real(diamonds_first);
+    assert_eq!(clubs, Run::Equal(cards(&["SQ", "CQ", "HQ"])));
+    assert_eq!(diamonds, Run::Equal(cards(&["SQ", "DQ", "HQ"])));
+    assert_eq!(clubs.get_score(), diamonds.get_score());
+
+    // suits missing from the preference fall back to the standard order
+    let only_hearts = RunCoercionStrategy { suit_preference: [Suit::Hearts; 4], ..clubs_first };
+    assert_eq!(set.coerce_to_real(only_hearts), Run::Equal(cards(&["SQ", "DQ", "HQ"])));
+}
+
+#[test]
+fn count_deadwood() {
+    let rules = ScoringRules::default();
+    let hand = Hand(cards(&["S2", "S3", "S4", "HQ", "DQ", "D9", "J"]));
+
+    // the joker and the queens make a set, which leaves the nine of diamonds
+    assert_eq!(hand.deadwood_count(), 1);
+    assert_eq!(hand.deadwood_score(&rules), 9);
+
+    let scattered = Hand(cards(&["S2", "D5", "HK"]));
+    assert_eq!(scattered.deadwood_count(), 3);
+    assert_eq!(scattered.deadwood_score(&rules), 17);
+}
+
+#[test]
+fn unchecked_runs() {
+    assert_eq!(Run::ascending_unchecked(cards(&["C9", "CX", "CJ"])), verify_run(cards(&["CJ", "C9", "CX"])).unwrap());
+    assert_eq!(Run::equal_unchecked(cards(&["S3", "C3", "H3"])), verify_run(cards(&["S3", "C3", "H3"])).unwrap());
+    assert!(!Run::Ascending(cards(&["S3", "C3", "H3"])).is_valid());
+}
+
+#[test]
+#[should_panic(expected = "invalid run")]
+#[cfg(debug_assertions)]
+fn unchecked_runs_are_asserted() {
+    Run::equal_unchecked(cards(&["S3", "C4", "H3"]));
+}
+
+#[test]
+fn most_wanted_cards() {
+    let rules = ScoringRules::default();
+    let hand = Hand(cards(&["S4", "S6", "HK", "D2"]));
+
+    let wanted = hand.most_wanted_cards(&rules);
+    assert_eq!(wanted.first(), Some(&card("S5")));
+    assert!(wanted.contains(&card("J")));
+    assert!(!wanted.contains(&card("S7")));
+}
+
+#[test]
+fn canonical_runs() {
+    assert_eq!(verify_run(cards(&["HK", "J", "CK"])), verify_run(cards(&["CK", "HK", "J"])));
+    assert_eq!(verify_run(cards(&["D5", "S5", "H5", "C5"])), verify_run(cards(&["C5", "H5", "S5", "D5"])));
+    assert_eq!(verify_run(cards(&["S8", "J", "S6"])), verify_run(cards(&["J", "S6", "S8"])));
+    assert_eq!(verify_run(cards(&["HK", "J", "CK"])).unwrap().cards(), cards(&["CK", "HK", "J"]));
+}
+
+#[test]
+fn discard_danger() {
+    let mut game = new_game(2, &GameConfig::default(), 134).unwrap();
+    game.table.melds.push(verify_run(cards(&["D5", "D6", "D7"])).unwrap());
+    game.players[1].melds.push(0);
+
+    let fitting = game.discard_danger(&card("D8"), 1);
+    let adjacent = game.discard_danger(&card("D9"), 1);
+    let unrelated = game.discard_danger(&card("SK"), 1);
+    assert_eq!(fitting, 1.0);
+    assert_eq!(game.discard_danger(&card("J"), 1), 1.0);
+    assert!(adjacent > unrelated);
+    assert!(fitting > adjacent);
+    assert!(unrelated > 0.0);
+}
+
+#[test]
+fn three_deck_composition() {
+    let deck = generate_deck_with_decks(3, true);
+    assert_eq!(deck.len(), 3 * SINGLE_DECK_SIZE);
+    assert_eq!(deck.iter().filter(|c| c.card_type == CardType::Joker).count(), 6);
+    for card in generate_single_deck(false).iter().filter(|c| c.card_type != CardType::Joker) {
+        assert_eq!(deck.iter().filter(|&c| c == card).count(), 3);
+    }
+
+    assert_eq!(validate_deck(&deck, 3), Ok(()));
+    assert_eq!(validate_deck(&generate_deck(true), 2), Ok(()));
+    assert_eq!(validate_deck(&deck, 2).unwrap_err().len(), 53);
+
+    let mut short = deck.clone();
+    let index = short.iter().position(|c| *c == card("C7")).unwrap();
+    short.remove(index);
+    assert_eq!(validate_deck(&short, 3), Err(vec![(card("C7"), 1)]));
+}
+
+#[test]
+fn stuck_hands() {
+    let hand = |notation: &[&str]| Hand(cards(notation));
+
+    let scattered = hand(&["SA", "S4", "S7", "SX", "D2", "D5", "D8", "DJ", "C3", "C6", "C9", "CQ", "HK"]);
+    assert!(scattered.find_melds().is_empty());
+    assert_eq!(scattered.opening_gap(), 4);
+    assert!(scattered.is_stuck(3));
+    assert!(!scattered.is_stuck(4));
+
+    // 18 points in melds, and the kings are one card away from 30 more
+    let close = hand(&["S5", "S6", "S7", "HK", "DK", "C2", "D9"]);
+    assert_eq!(close.opening_gap(), 1);
+    assert!(!close.is_stuck(0));
+
+    let pairs = hand(&["HK", "DK", "CQ", "SQ", "S2", "H6", "C9"]);
+    assert!(pairs.find_melds().is_empty());
+    assert_eq!(pairs.opening_gap(), 2);
+    assert!(!pairs.is_stuck(3));
+
+    let open = hand(&["HK", "DK", "CK", "S5", "S6", "S7"]);
+    assert!(open.best_partition().0.iter().map(|r| u16::from(r.get_score())).sum::<u16>() >= OPENING_SCORE);
+    assert_eq!(open.opening_gap(), 0);
+}
+
+#[test]
+fn card_ids_out_of_range() {
+    assert_eq!(Card::from_id(200), None);
+    assert_eq!(Card::from_id(53), None);
+    assert_eq!(Card::from_bytes(&[200]), None);
+    assert_eq!(Hand::from_bytes(&[2, 0, 200]).map(|h| h.0), None);
+    assert_eq!(Run::from_bytes(&[0, 3, 0, 1, 200]), None);
+
+    for id in 0..=255u8 {
+        match Card::from_id(id) {
+            Some(card) => {
+                assert!(id <= 52);
+                assert_eq!(card.to_id(), id);
+            },
+            None => assert!(id > 52)
+        }
+    }
+}
+
+#[test]
+fn best_single_meld() {
+    let hand = |notation: &[&str]| Hand(cards(notation));
+    let rules = ScoringRules::default();
+
+    let mixed = hand(&["S2", "D2", "C2", "HJ", "HQ", "HK", "D7"]);
+    let best = mixed.best_single_meld(&rules).unwrap();
+    assert!(best.is_sequence());
+    assert_eq!(best.get_score(), 30);
+
+    assert_eq!(hand(&["S2", "D5", "C9"]).best_single_meld(&rules), None);
+    assert_eq!(hand(&[]).best_single_meld(&rules), None);
+}
+
+#[test]
+fn ace_high_and_low() {
+    let low = verify_run(cards(&["S3", "SA", "S4", "S2"])).unwrap();
+    assert_eq!(low.cards(), &cards(&["SA", "S2", "S3", "S4"])[..]);
+    assert_eq!(low.get_score(), 10);
+
+    let high = verify_run(cards(&["SA", "SJ", "SK", "SQ"])).unwrap();
+    assert_eq!(high.cards(), &cards(&["SJ", "SQ", "SK", "SA"])[..]);
+    assert_eq!(high.get_score(), 41);
+    assert!(high.is_valid());
+
+    let jokered = verify_run(cards(&["SA", "J", "SQ"])).unwrap();
+    assert_eq!(jokered.cards(), &cards(&["SQ", "SA", "J"])[..]);
+
+    assert_eq!(verify_run(cards(&["SK", "SA", "S2"])), Err(RunError::Invalid));
+    assert_eq!(verify_run(cards(&["SQ", "SK", "SA", "S2"])), Err(RunError::Invalid));
+    assert_eq!(verify_run(cards(&["SK", "SA", "J", "S3"])), Err(RunError::Invalid));
+}
+
+#[test]
+fn duplicate_melds() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+
+    let mut table = Table { melds: vec![run(&["S4", "S5", "S6"]), run(&["D5", "H5", "C5"]), run(&["S5", "S6", "S7"])], ..Table::default() };
+    assert!(table.duplicate_melds().is_empty());
+
+    table.melds.push(run(&["S3", "S4", "S5"]));
+    assert_eq!(table.duplicate_melds(), vec![(0, 2), (0, 3), (2, 3)]);
+    assert_eq!(validate_card_budget(&[card("S5"); 3], 2), Err(vec![card("S5")]));
+
+    let jokers = Table { melds: vec![run(&["S2", "S3", "J", "J", "J"]), run(&["DK", "HK", "J", "J"])], ..Table::default() };
+    assert_eq!(jokers.duplicate_melds(), vec![(0, 1)]);
+}
+
+#[test]
+fn suit_run_potential() {
+    let hand = Hand(cards(&["S3", "S4", "S6", "S7", "S8", "SX", "H9", "HJ", "J"]));
+
+    let groups = hand.group_by_suit();
+    assert_eq!(groups[&Suit::Spades].len(), 6);
+    assert_eq!(groups[&Suit::JokerSuit], vec![card("J")]);
+
+    let potential = hand.suit_run_potential();
+    assert_eq!(potential.len(), 2);
+    assert_eq!(potential[&Suit::Spades], 5);
+    assert_eq!(potential[&Suit::Hearts], 2);
+    assert!(!potential.contains_key(&Suit::Diamonds));
+
+    let aces = Hand(cards(&["DQ", "DA", "D2", "D3", "D3"]));
+    assert_eq!(aces.suit_run_potential()[&Suit::Diamonds], 3);
+
+    // the same ace can't sit at both ends
+    let full = Hand(generate_single_deck(false).into_iter().filter(|c| c.suit == Suit::Clubs).collect());
+    assert_eq!(full.suit_run_potential()[&Suit::Clubs], 13);
+    let gapped = Hand(cards(&["SA", "S3", "S4", "S5", "S6", "S7", "S8", "S9", "SX", "SJ", "SQ", "SK"]));
+    assert_eq!(gapped.suit_run_potential()[&Suit::Spades], 12);
+}
+
+#[test]
+fn melds_enabled_by() {
+    let hand = Hand(cards(&["H5", "H6", "D9", "C9", "SK"]));
+
+    assert_eq!(hand.melds_enabled_by(&card("H7")), vec![verify_run(cards(&["H5", "H6", "H7"])).unwrap()]);
+    assert!(hand.melds_enabled_by(&card("SQ")).is_empty());
+    assert_eq!(hand.melds_enabled_by(&card("S9")).len(), 1);
+
+    let melded = Hand(cards(&["H5", "H6", "H7"]));
+    assert_eq!(melded.melds_enabled_by(&card("H8")), vec![
+        verify_run(cards(&["H6", "H7", "H8"])).unwrap(),
+        verify_run(cards(&["H5", "H6", "H7", "H8"])).unwrap()
+    ]);
+}
+
+#[test]
+fn full_hand_penalty() {
+    let hand = |notation: &[&str]| Hand(cards(notation));
+    let rules = ScoringRules { full_hand_penalty: 150, hand_size: 5, ..ScoringRules::default() };
+
+    assert_eq!(hand(&["S2", "S3", "D9", "HK", "J"]).score_with(&rules), 150);
+    assert_eq!(hand(&["S2", "S3", "D9", "HK"]).score_with(&rules), 24);
+    assert_eq!(hand(&["SA"]).score_with(&rules), 1);
+
+    let full = Hand(generate_deck(false).into_iter().take(13).collect());
+    assert_eq!(full.score_with(&rules), full.0.iter().map(|c| u16::from(c.score())).sum::<u16>());
+    assert_eq!(full.score_with(&ScoringRules::default()), 100);
+}
+
+#[test]
+fn mixed_suits() {
+    assert_eq!(verify_run(cards(&["S2", "D3", "S4"])), Err(RunError::MixedSuits));
+    assert_eq!(verify_run(cards(&["S2", "J", "D4"])), Err(RunError::MixedSuits));
+    assert_eq!(verify_run(cards(&["S2", "J", "S4"])).map(|r| r.is_sequence()), Ok(true));
+    assert_eq!(RunError::MixedSuits.to_string(), "the cards of an ascending run must all be of the same suit");
+}
+
+#[test]
+fn take_cards() {
+    let mut deck = generate_single_deck(false);
+    let original = deck.clone();
+
+    assert_eq!(crate::utils::take_cards(&mut deck, SINGLE_DECK_SIZE + 1), Err(SINGLE_DECK_SIZE));
+    assert_eq!(deck, original);
+
+    let top = crate::utils::take_cards(&mut deck, 3).unwrap();
+    assert_eq!(top, original.iter().rev().take(3).copied().collect::<Vec<_>>());
+
+    let rest = crate::utils::take_cards(&mut deck, SINGLE_DECK_SIZE - 3).unwrap();
+    assert!(deck.is_empty());
+    assert_deck_conservation(&original, &[&top, &rest]);
+    assert_eq!(crate::utils::take_cards(&mut deck, 0), Ok(Vec::new()));
+
+    let mut deck = original.clone();
+    assert_eq!(Hand::draw_n(&mut deck, 5).unwrap().0.len(), 5);
+    assert_eq!(Hand::draw_n(&mut deck, SINGLE_DECK_SIZE).map(|h| h.0), Err(SINGLE_DECK_SIZE - 5));
+    assert_eq!(Hand::draw(&mut original.clone()).0, original.iter().rev().take(13).copied().collect::<Vec<_>>());
+}
+
+#[test]
+fn runs_as_keys() {
+    use std::collections::{BTreeSet, HashSet};
+    let runs = [
+        verify_run(cards(&["HQ", "SQ", "DQ"])).unwrap(),
+        verify_run(cards(&["D5", "D3", "D4"])).unwrap(),
+        verify_run(cards(&["S7", "S8", "J"])).unwrap(),
+        verify_run(cards(&["DQ", "SQ", "HQ"])).unwrap(),
+        verify_run(cards(&["S2", "D2", "C2"])).unwrap()
+    ];
+
+    let set: BTreeSet<Run> = runs.iter().cloned().collect();
+    assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![
+        Run::Ascending(cards(&["S7", "S8", "J"])),
+        Run::Ascending(cards(&["D3", "D4", "D5"])),
+        Run::Equal(cards(&["S2", "D2", "C2"])),
+        Run::Equal(cards(&["SQ", "DQ", "HQ"]))
+    ]);
+
+    assert_eq!(runs.iter().cloned().collect::<HashSet<Run>>().len(), 4);
+}
+
+#[test]
+fn core_cards() {
+    let rules = ScoringRules::default();
+
+    let hand = Hand(cards(&["D9", "C7", "C8", "C6", "S2", "HK", "SK", "DK"]));
+    let core = hand.core_cards(&rules);
+    assert_eq!(core.len(), 6);
+    for card in cards(&["C6", "C7", "C8", "HK", "SK", "DK"]) { assert!(core.contains(&card)); }
+
+    let (_, deadwood) = hand.best_partition();
+    assert_eq!(core.len() + deadwood.len(), hand.0.len());
+    assert!(deadwood.iter().all(|c| !core.contains(c)));
+
+    assert!(Hand(cards(&["S2", "D9", "HK"])).core_cards(&rules).is_empty());
+}
+
+#[test]
+fn game_fingerprint() {
+    let strategies: [&dyn Strategy; 2] = [&GreedyStrategy, &GreedyStrategy];
+    let play = |seed: u64| {
+        let mut game = new_game(2, &GameConfig::default(), seed).unwrap();
+        for _ in 0..10 { if game.play_turn(strategies[game.current_player]) { break; } }
+        game
+    };
+
+    let game = play(148);
+    assert!(!game.events.is_empty());
+    assert!(matches!(game.events[0], GameEvent::Draw { player: 1, .. }));
+    assert_eq!(play(148).fingerprint(), game.fingerprint());
+    assert_ne!(play(149).fingerprint(), game.fingerprint());
+
+    let mut altered = game.clone();
+    let last = altered.events.len() - 1;
+    if let GameEvent::Discard { card: discarded, .. } = &mut altered.events[last] {
+        *discarded = if *discarded == card("S2") { card("S3") } else { card("S2") };
+    }
+    assert_ne!(altered.fingerprint(), game.fingerprint());
+
+    let mut unseeded = game.clone();
+    unseeded.seed = None;
+    assert_ne!(unseeded.fingerprint(), game.fingerprint());
+}
+
+#[test]
+fn hand_layout() {
+    let hand = Hand(cards(&["HQ", "D5", "SQ", "C9", "D3", "DQ", "S2", "D4"]));
+    assert_eq!(hand.layout(), HandLayout {
+        melds: vec![cards(&["D3", "D4", "D5"]), cards(&["SQ", "DQ", "HQ"])],
+        deadwood: cards(&["S2", "C9"])
+    });
+
+    let layout = Hand(cards(&["HK", "S3"])).layout();
+    assert!(layout.melds.is_empty());
+    assert_eq!(layout.deadwood, cards(&["S3", "HK"]));
+}
+
+#[test]
+fn jokers_in_opening() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+    let jokered = [run(&["HJ", "HQ", "J"]), run(&["SX", "DX", "CX"])];
+    let real = [run(&["HJ", "HQ", "HK"]), run(&["SX", "DX", "CX"])];
+    let hand = Hand(cards(&["HJ", "HQ", "HK", "J", "SX", "DX", "CX", "S2"]));
+
+    let lenient = GameConfig::default();
+    assert!(lenient.jokers_allowed_in_opening);
+    assert_eq!(lenient.validate_opening(&jokered, &hand), Ok(()));
+    assert_eq!(lenient.validate_opening(&real, &hand), Ok(()));
+    assert_eq!(lenient.validate_opening(&real[..1], &hand), Err(OpeningError::TooFewPoints { scored: 30 }));
+
+    let strict = GameConfig { jokers_allowed_in_opening: false, ..GameConfig::default() };
+    assert_eq!(strict.validate_opening(&jokered, &hand), Err(OpeningError::JokerNotAllowed));
+    assert_eq!(strict.validate_opening(&real, &hand), Ok(()));
+}
+
+#[test]
+fn longest_ascending_run() {
+    let hand = Hand(cards(&["S4", "S7", "S5", "S8", "S6", "S6", "HJ", "HQ", "HK", "HA", "D2", "D3", "J", "CX"]));
+    assert_eq!(hand.longest_ascending_run(), Some(Run::Ascending(cards(&["S4", "S5", "S6", "S7", "S8"]))));
+
+    let high = Hand(cards(&["HQ", "HK", "HA", "D2", "D3", "D4"]));
+    assert_eq!(high.longest_ascending_run(), Some(Run::Ascending(cards(&["HQ", "HK", "HA"]))));
+
+    assert_eq!(Hand(cards(&["S2", "S3", "J", "D4", "S5"])).longest_ascending_run(), None);
+
+    // the ace can't be played at both ends of a full suit at once
+    let suit = cards(&["SA", "S2", "S3", "S4", "S5", "S6", "S7", "S8", "S9", "SX", "SJ", "SQ", "SK"]);
+    assert_eq!(Hand(suit.clone()).longest_ascending_run(), Some(Run::Ascending(suit)));
+}
+
+#[test]
+fn discard_value() {
+    let mut table = Table::default();
+    assert_eq!(table.discard_value(&ScoringRules::default()), 0);
+
+    for notation in ["S2", "HK", "DA", "J", "C7"] { table.discard(card(notation)); }
+    assert_eq!(table.discard_value(&ScoringRules::default()), 2 + 10 + 11 + 25 + 7);
+    assert_eq!(table.discard_value(&ScoringRules { joker_value: 50, ..ScoringRules::default() }), 2 + 10 + 11 + 50 + 7);
+}
+
+#[test]
+fn runs_of_explicit_kind() {
+    assert_eq!(Run::from_cards(RunKind::Sequence, cards(&["S4", "S2", "S3"])), Ok(Run::Ascending(cards(&["S2", "S3", "S4"]))));
+    assert_eq!(Run::from_cards(RunKind::Set, cards(&["H9", "S9", "J"])), Ok(Run::Equal(cards(&["S9", "H9", "J"]))));
+    assert_eq!(Run::from_cards(RunKind::Set, cards(&["S2", "S3", "S4"])), Err(RunError::Invalid));
+    assert_eq!(Run::from_cards(RunKind::Sequence, cards(&["S9", "H9", "D9"])), Err(RunError::MixedSuits));
+
+    // a single real card can't be classified by verify_run(), but it can be given a kind
+    assert_eq!(verify_run(cards(&["J", "SK", "J"])), Err(RunError::Ambiguous));
+    let sequence = Run::from_cards(RunKind::Sequence, cards(&["J", "SK", "J"])).unwrap();
+    assert_eq!(sequence, Run::Ascending(cards(&["SK", "J", "J"])));
+    assert_eq!(sequence.kind(), RunKind::Sequence);
+    assert!(sequence.is_valid());
+    assert_eq!(Run::from_cards(RunKind::Set, cards(&["J", "SK", "J"])).map(|r| r.kind()), Ok(RunKind::Set));
+    assert_eq!(Run::from_cards(RunKind::Set, cards(&["J", "SK", "J", "J", "J"])), Err(RunError::Invalid));
+    assert_eq!(Run::from_cards(RunKind::Set, cards(&["J", "J", "J"])), Err(RunError::Ambiguous));
+}
+
+#[test]
+fn estimated_turns_remaining() {
+    let game = Game::deal(generate_deck(false), 3).unwrap();
+    assert_eq!(game.table.stock.len(), FULL_DECK_SIZE - 3 * 13 - 1);
+    assert_eq!(game.estimated_turns_remaining(), 68);
+    assert_eq!(game.estimated_rounds_remaining(), 22);
+
+    let mut game = Game::deal(generate_deck(false)[..28].to_vec(), 2).unwrap();
+    assert_eq!(game.estimated_turns_remaining(), 1);
+    assert_eq!(game.estimated_rounds_remaining(), 0);
+    game.table.draw_from_stock();
+    assert_eq!(game.estimated_turns_remaining(), 0);
+}
+
+#[test]
+fn overlapping_melds() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+
+    let overlapping = [run(&["SA", "DA", "HA"]), run(&["SA", "S2", "S3"])];
+    let single = Hand(cards(&["SA", "DA", "HA", "S2", "S3", "CK"]));
+    assert!(!melds_are_disjoint(&overlapping));
+    assert_eq!(GameConfig::default().validate_opening(&overlapping, &single), Err(OpeningError::OverlappingMelds(card("SA"))));
+
+    // with two decks, a hand can hold both copies of SK, one for each meld
+    let sharing = [run(&["SK", "DK", "HK"]), run(&["SJ", "SQ", "SK"])];
+    let double = Hand(cards(&["SK", "DK", "HK", "SJ", "SQ", "SK", "C2"]));
+    assert_eq!(GameConfig::default().validate_opening(&sharing, &double), Ok(()));
+    assert_eq!(GameConfig::default().validate_opening(&sharing, &Hand(cards(&["SK", "DK", "HK", "SJ", "SQ", "C2"]))),
+               Err(OpeningError::OverlappingMelds(card("SK"))));
+
+    let disjoint = [run(&["CA", "DA", "HA"]), run(&["SA", "S2", "S3"]), run(&["HK", "HQ", "J"]), run(&["D9", "J", "DJ"])];
+    let hand = Hand(disjoint.iter().flat_map(|r| r.cards().iter().copied()).collect());
+    assert!(melds_are_disjoint(&disjoint));
+    assert_eq!(GameConfig::default().validate_opening(&disjoint, &hand), Ok(()));
+    assert!(melds_are_disjoint(&[]));
+}
+
+#[test]
+fn open_with_both_copies_of_a_card() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+    let mut game = Game::deal(generate_deck(false), 2).unwrap();
+    rig_hand(&mut game, 1, &cards(&["SK", "DK", "HK", "SK", "SJ", "SQ"]));
+    rig_top(&mut game, DrawSource::Stock, "C2");
+    game.apply(Move::DrawStock).unwrap();
+
+    let melds = vec![run(&["SK", "DK", "HK"]), run(&["SJ", "SQ", "SK"])];
+    assert!(game.legal_moves().contains(&Move::Meld(melds.clone())));
+    assert_eq!(game.apply(Move::Meld(melds)), Ok(()));
+    assert_eq!(game.players[1].hand.0, cards(&["C2"]));
+}
+
+#[test]
+fn public_table() {
+    let game = new_game(2, &GameConfig::default(), 156).unwrap();
+    let view = game.table.public_view();
+
+    assert_eq!(view.stock_count, game.table.stock.len());
+    assert_eq!(view.discard, game.table.discard);
+    assert_eq!(view.melds, game.table.melds);
+
+    // the view only carries the count, so it stays the same whatever the stock holds
+    let mut shuffled = game.table.clone();
+    shuffled.stock.reverse();
+    assert_ne!(shuffled.stock, game.table.stock);
+    assert_eq!(shuffled.public_view(), view);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn public_table_is_serializable() {
+    fn serializable<T: serde::Serialize>(_: &T) {}
+
+    serializable(&new_game(2, &GameConfig::default(), 156).unwrap().table.public_view());
+}
+
+#[test]
+fn enables_opponent_out() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+    let mut game = Game::deal(generate_deck(false), 3).unwrap();
+    assert_eq!(game.current_player, 1);
+    game.table.melds = vec![run(&["H4", "H5", "H6"]), run(&["SK", "DK", "CK"])];
+    game.players[2].melds = vec![0, 1];
+    game.players[2].hand = Hand(cards(&["D9", "DX"]));
+    game.players[0].hand = Hand(cards(&["S2"]));
+
+    // player 2 would still hold a card after laying off and discarding, and player 0 hasn't opened
+    assert_eq!(game.enables_opponent_out(&card("H7")), None);
+
+    game.players[2].hand.0.pop();
+    assert_eq!(game.enables_opponent_out(&card("H7")), Some(2));
+    assert_eq!(game.enables_opponent_out(&card("H3")), Some(2));
+    assert_eq!(game.enables_opponent_out(&card("J")), Some(2));
+    assert_eq!(game.enables_opponent_out(&card("D2")), None);
+
+    // once player 0 has opened, they can lay off onto player 2's melds too
+    game.players[2].hand.0.push(card("DX"));
+    game.players[0].melds = vec![1];
+    game.players[2].melds = vec![0];
+    assert_eq!(game.enables_opponent_out(&card("HK")), Some(0));
+
+    // only the size of a hand counts, never what it holds
+    game.players[0].hand = Hand(cards(&["CA"]));
+    assert_eq!(game.enables_opponent_out(&card("HK")), Some(0));
+
+    game.table.freeze_on_joker = true;
+    assert_eq!(game.enables_opponent_out(&card("J")), None);
+    assert_eq!(game.enables_opponent_out(&card("HK")), Some(0));
+
+    // a frozen pile stays frozen, whatever lands on top of it
+    game.table.discard(card("J"));
+    assert!(game.table.frozen);
+    assert_eq!(game.enables_opponent_out(&card("HK")), None);
+}
+
+#[test]
+fn scripted_deck() {
+    let deck = generate_deck(false);
+    let config = GameConfig { jokers_allowed_in_opening: false, ..GameConfig::default() };
+    let game = Game::from_deck(deck.clone(), 2, &config).unwrap();
+
+    assert_eq!(game.config, config);
+    assert_eq!(game.seed, None);
+    let dealt = |from: usize| deck[from..from + 13].iter().rev().copied().collect::<Vec<_>>();
+    assert_eq!(game.players[0].hand.0, dealt(FULL_DECK_SIZE - 13));
+    assert_eq!(game.players[1].hand.0, dealt(FULL_DECK_SIZE - 26));
+    assert_eq!(game.players[0].hand.0[0], deck[FULL_DECK_SIZE - 1]);
+    assert_eq!(game.table.discard, vec![deck[FULL_DECK_SIZE - 27]]);
+    assert_eq!(game.table.stock, deck[..FULL_DECK_SIZE - 27]);
+
+    assert_eq!(Game::from_deck(deck[..27].to_vec(), 2, &config).err(), Some(SetupError::DeckTooSmall { needed: 28, available: 27 }));
+}
+
+#[test]
+fn overlong_sequences() {
+    let mut cards: Vec<Card> = cards(&["S2", "S3"]);
+    cards.extend([card("J"); 12]);
+    assert_eq!(verify_run(cards.clone()).map(|r| r.cards().len()), Ok(14));
+
+    cards.push(card("J"));
+    assert_eq!(verify_run(cards.clone()), Err(RunError::TooLong));
+    assert_eq!(Run::from_cards(RunKind::Sequence, cards[1..].to_vec()).map(|r| r.cards().len()), Ok(14));
+    assert_eq!(Run::from_cards(RunKind::Sequence, cards), Err(RunError::TooLong));
+
+    let mut full: Vec<Card> = generate_single_deck(false).into_iter().filter(|c| c.suit == Suit::Hearts).collect();
+    full.push(card("J"));
+    let run = verify_run(full).unwrap();
+    assert_eq!(run.to_display_cards(&RunCoercionStrategy::default()).last(), Some(&card("HA")));
+}
+
+#[test]
+fn distance_to_out() {
+    let hand = |notation: &[&str]| Hand(cards(notation));
+
+    assert_eq!(hand(&["H5", "H6", "H7", "S9", "D9", "C9", "CK"]).distance_to_out(), 0);
+
+    let waiting = hand(&["H5", "H6", "H7", "S9", "D9", "CK"]);
+    assert!(!waiting.can_go_out());
+    assert_eq!(waiting.distance_to_out(), 1);
+
+    // two pairs and a single, so it takes one swap to complete each of the pairs
+    assert_eq!(hand(&["H5", "H6", "S9", "D9", "CK"]).distance_to_out(), 2);
+
+    let scattered = hand(&["SA", "S4", "S7", "SX", "D2", "D5", "D8", "DJ", "C3", "C6", "C9", "CQ", "HK"]);
+    assert_eq!(scattered.distance_to_out(), 12);
+}
+
+#[test]
+fn legal_moves() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+
+    let mut game = Game::deal(generate_deck(false), 2).unwrap();
+    assert_eq!(game.phase, TurnPhase::Draw);
+    assert_eq!(game.legal_moves(), vec![Move::DrawStock, Move::DrawDiscard]);
+
+    game.players[1].hand = Hand(cards(&["HJ", "HQ", "HK", "SK", "DK", "CK", "S2", "S2"]));
+    game.phase = TurnPhase::Play;
+    let moves = game.legal_moves();
+    assert!(!moves.contains(&Move::DrawStock));
+    assert!(moves.contains(&Move::Meld(vec![run(&["HJ", "HQ", "HK"]), run(&["SK", "DK", "CK"])])));
+    assert!(!moves.contains(&Move::Meld(vec![run(&["HJ", "HQ", "HK"])])), "a single meld of 30 points isn't enough to open");
+    assert_eq!(moves.iter().filter(|m| matches!(m, Move::Discard(_))).count(), 7);
+    assert!(!moves.iter().any(|m| matches!(m, Move::LayOff { .. })), "laying off takes opening first");
+
+    game.table.melds.push(run(&["S3", "S4", "S5"]));
+    game.players[1].melds.push(0);
+    let moves = game.legal_moves();
+    assert!(moves.contains(&Move::Meld(vec![run(&["HJ", "HQ", "HK"])])));
+    assert!(moves.contains(&Move::LayOff { meld: 0, card: card("S2") }));
+    assert_eq!(moves.iter().filter(|m| matches!(m, Move::LayOff { .. })).count(), 1);
+
+    game.players[1].hand = Hand(cards(&["S6"]));
+    assert_eq!(game.legal_moves(), vec![Move::Discard(card("S6"))]);
+}
+
+#[test]
+fn apply_moves() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+
+    let mut game = Game::deal(generate_single_deck(false), 2).unwrap();
+    rig_hand(&mut game, 1, &cards(&["HJ", "HQ", "HK", "SK", "DK", "CK", "S2", "D7"]));
+    rig_top(&mut game, DrawSource::Stock, "S3");
+
+    assert_eq!(game.apply(Move::Discard(card("D7"))), Err(MoveError::WrongPhase));
+    assert_eq!(game.apply(Move::DrawStock), Ok(()));
+    assert_eq!(game.phase, TurnPhase::Play);
+    assert_eq!(game.apply(Move::DrawDiscard), Err(MoveError::WrongPhase));
+
+    assert_eq!(game.apply(Move::Meld(vec![run(&["HJ", "HQ", "HK"])])), Err(MoveError::Opening(OpeningError::TooFewPoints { scored: 30 })));
+    assert_eq!(game.apply(Move::Meld(vec![run(&["HJ", "HQ", "HK"]), run(&["SA", "S2", "S3"])])), Err(MoveError::NotInHand(card("SA"))));
+    assert_eq!(game.apply(Move::Meld(vec![Run::Ascending(cards(&["S2", "D7", "S3"]))])), Err(MoveError::InvalidMeld(RunError::MixedSuits)));
+    assert_eq!(game.apply(Move::LayOff { meld: 0, card: card("S2") }), Err(MoveError::CannotLayOff));
+    assert_eq!(game.players[1].hand.0.len(), 9);
+
+    // the cards of a meld may come in any order, but go onto the table in canonical order
+    let unsorted = vec![Run::Ascending(cards(&["HK", "HJ", "HQ"])), Run::Equal(cards(&["CK", "SK", "DK"]))];
+    assert_eq!(game.apply(Move::Meld(unsorted)), Ok(()));
+    assert_eq!(game.players[1].melds, vec![0, 1]);
+    let canonical = vec![run(&["HJ", "HQ", "HK"]), run(&["SK", "DK", "CK"])];
+    assert_eq!(game.table.melds, canonical);
+    assert_eq!(game.events[1], GameEvent::Meld { player: 1, runs: canonical });
+    assert_eq!(game.apply(Move::Meld(vec![run(&["S2", "S3", "J"])])), Err(MoveError::NotInHand(card("J"))));
+    assert_eq!(game.apply(Move::LayOff { meld: 1, card: card("S2") }), Err(MoveError::CannotLayOff));
+    assert_eq!(game.apply(Move::LayOff { meld: 0, card: card("HX") }), Err(MoveError::NotInHand(card("HX"))));
+    assert!(swap_out(&mut game, 1, card("HX"), None));
+    game.players[1].hand.0.push(card("HX"));
+    assert_eq!(game.apply(Move::LayOff { meld: 0, card: card("HX") }), Ok(()));
+    assert_eq!(game.table.melds[0], run(&["HX", "HJ", "HQ", "HK"]));
+
+    assert_eq!(game.apply(Move::Discard(card("D7"))), Ok(()));
+    assert_eq!((game.current_player, game.phase), (0, TurnPhase::Draw));
+    assert_eq!(game.table.peek_discard(), Some(&card("D7")));
+    assert_eq!(game.players[1].hand.0, cards(&["S2", "S3"]));
+    assert_eq!(game.events.len(), 4);
+    assert!(matches!(game.events[1], GameEvent::Meld { player: 1, .. }));
+}
+
+#[test]
+fn initial_joker() {
+    let mut deck = generate_deck(false);
+    let flipped = FULL_DECK_SIZE - 27;
+    // the jokers of the first single deck are at its end, deep down in the stock
+    deck.swap(flipped, SINGLE_DECK_SIZE - 2);
+    assert_eq!(deck[flipped], card("J"));
+    let next = deck[flipped - 1];
+
+    let allowed = Game::from_deck(deck.clone(), 2, &GameConfig { initial_joker: InitialJoker::Allow, ..GameConfig::default() }).unwrap();
+    assert_eq!(allowed.table.discard, vec![card("J")]);
+
+    let buried = Game::from_deck(deck.clone(), 2, &GameConfig::default()).unwrap();
+    assert_eq!(buried.config.initial_joker, InitialJoker::Bury);
+    assert_eq!(buried.table.discard, vec![next]);
+    assert_eq!(buried.table.stock[0], card("J"));
+    assert_eq!(buried.table.stock.len(), allowed.table.stock.len());
+
+    // the next card is a joker as well, so both get buried
+    deck.swap(flipped - 1, SINGLE_DECK_SIZE - 1);
+    let buried = Game::deal(deck.clone(), 2).unwrap();
+    assert_eq!(&buried.table.stock[..2], &[card("J"), card("J")]);
+    assert_ne!(buried.table.discard[0].card_type, CardType::Joker);
+}
+
+#[test]
+fn round_score() {
+    let hand = |notation: &[&str]| Hand(cards(notation));
+    let rules = ScoringRules::default();
+
+    let melded = hand(&["H5", "H6", "H7", "SK", "DK", "CK", "D2", "J"]);
+    assert_eq!(melded.score(), 5 + 6 + 7 + 30 + 2 + 25);
+    assert_eq!(melded.round_score(&rules), 2);
+
+    assert_eq!(hand(&["H5", "H6", "H7", "SA"]).round_score(&rules), 1);
+    assert_eq!(hand(&["H5", "H6", "H7", "D9", "J"]).round_score(&ScoringRules { joker_value: 50, ..rules }), 9);
+    assert_eq!(hand(&["S9", "J"]).round_score(&ScoringRules { joker_value: 50, ..rules }), 59);
+
+    let scattered = hand(&["SA", "S4", "S7", "SX", "D2", "D5", "D8", "DJ", "C3", "C6", "C9", "CQ", "HK"]);
+    assert_eq!(scattered.round_score(&rules), 100);
+
+    // melding three cards out of sixteen leaves thirteen, which isn't a full hand
+    let mut melded = scattered.clone();
+    melded.0.extend(cards(&["H4", "H5", "H6"]));
+    assert_eq!(melded.round_score(&rules), scattered.0.iter().map(|c| u16::from(c.score_with(&rules))).sum::<u16>());
+    // while a hand that's just drawn a fourteenth card doesn't dodge the penalty
+    melded.0.truncate(14);
+    assert_eq!(melded.round_score(&rules), 100);
+}
+
+#[test]
+fn draw_from_empty_discard() {
+    let mut table = Table::default();
+    assert_eq!(table.draw_from_discard(), None);
+
+    table.discard(card("H8"));
+    assert_eq!(table.draw_from_discard(), Some(card("H8")));
+    assert_eq!(table.draw_from_discard(), None);
+    assert_eq!(table.discard_len(), 0);
+
+    let mut game = Game::deal(generate_deck(false), 2).unwrap();
+    game.table.discard.clear();
+    assert_eq!(game.apply(Move::DrawDiscard), Err(MoveError::EmptyPile));
+    assert_eq!(game.phase, TurnPhase::Draw);
+}
+
+#[test]
+fn asset_keys() {
+    assert_eq!(card("SA").asset_key(), "spades_ace");
+    assert_eq!(card("HX").asset_key(), "hearts_10");
+    assert_eq!(card("D2").asset_key(), "diamonds_2");
+    assert_eq!(card("CQ").asset_key(), "clubs_queen");
+    assert_eq!(card("J").asset_key(), "joker");
+
+    let deck = generate_single_deck(false);
+    let keys: std::collections::HashSet<String> = deck.iter().map(|c| c.asset_key()).collect();
+    assert_eq!(keys.len(), SINGLE_DECK_SIZE - 1, "every card but the second joker has a key of its own");
+    assert!(keys.iter().all(|k| k.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')));
+}
+
+#[test]
+fn jokers_next_to_aces() {
+    let lowest = RunCoercionStrategy { highest_possible: false, ..RunCoercionStrategy::default() };
+    let highest = RunCoercionStrategy { highest_possible: true, ..RunCoercionStrategy::default() };
+
+    // the joker can't wrap around to the king, so it has to go above the two
+    let low = verify_run(cards(&["J", "SA", "S2"])).unwrap();
+    assert_eq!(low.to_display_cards(&lowest), cards(&["SA", "S2", "S3"]));
+    assert_eq!(low.to_display_cards(&highest), cards(&["SA", "S2", "S3"]));
+    assert_eq!(low.get_score(), 6);
+
+    // the joker can stand for the low ace, since nothing has to go below it
+    let two = verify_run(cards(&["J", "S2", "S3"])).unwrap();
+    assert_eq!(two.to_display_cards(&lowest), cards(&["SA", "S2", "S3"]));
+    assert_eq!(two.to_display_cards(&highest), cards(&["S2", "S3", "S4"]));
+
+    let high = verify_run(cards(&["SQ", "SK", "J"])).unwrap();
+    assert_eq!(high.to_display_cards(&highest), cards(&["SQ", "SK", "SA"]));
+    assert_eq!(high.to_display_cards(&lowest), cards(&["SJ", "SQ", "SK"]));
+    assert_eq!(verify_run(cards(&["SK", "SA", "J"])).unwrap().to_display_cards(&highest), cards(&["SQ", "SK", "SA"]));
+
+    assert_eq!(verify_run(cards(&["SK", "J", "S2"])), Err(RunError::Invalid));
+}
+
+#[test]
+fn buried_cards() {
+    let mut table = Table::default();
+    assert!(table.buried_cards().is_empty());
+
+    table.discard(card("S4"));
+    assert!(table.buried_cards().is_empty());
+
+    for notation in ["HK", "J", "D9"] { table.discard(card(notation)); }
+    assert_eq!(table.buried_cards(), cards(&["S4", "HK", "J"]));
+
+    table.replenish_stock();
+    assert!(table.buried_cards().is_empty());
+}
+
+#[test]
+fn opening_options() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+    let rules = ScoringRules::default();
+
+    let hand = Hand(cards(&["SK", "DK", "CK", "HK", "C9", "CX", "CJ", "S2"]));
+    let options = hand.opening_options(OPENING_SCORE, &rules);
+    assert_eq!(options.len(), 6);
+    assert_eq!(options[0], vec![run(&["SK", "DK", "CK", "HK"])]);
+    assert!(options[1..5].iter().all(|o| o.len() == 2 && o[0] == run(&["C9", "CX", "CJ"]) && o[1].cards().len() == 3));
+    assert_eq!(options[5], vec![run(&["C9", "CX", "CJ"]), run(&["SK", "DK", "CK", "HK"])]);
+
+    assert_eq!(hand.opening_options(61, &rules).len(), 1);
+    assert!(hand.opening_options(71, &rules).is_empty());
+    assert!(Hand(cards(&["SK", "DK", "CK", "HK"])).opening_options(OPENING_SCORE, &rules).is_empty(), "there has to be a card left to discard");
+}
+
+#[test]
+fn dead_cards() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+
+    let mut game = Game::deal(generate_deck(true), 2).unwrap();
+    game.players[0].hand = Hand(vec![card("S9"), card("D3")]);
+    game.players[1].hand = Hand(vec![card("H5"), card("H6"), card("C2")]);
+    game.table.discard = vec![card("D4")];
+    game.table.melds.push(run(&["SA", "DA", "HA"]));
+
+    assert!(!game.is_card_dead(&card("H7")));
+    game.table.melds.push(run(&["H7", "H8", "H9"]));
+    assert!(!game.is_card_dead(&card("H7")));
+    game.table.discard(card("H7"));
+    assert!(game.is_card_dead(&card("H7")));
+
+    // the current player's own hand counts, the other players' hands don't
+    game.table.discard(card("C2"));
+    assert!(game.is_card_dead(&card("C2")));
+    game.current_player = 0;
+    assert!(!game.is_card_dead(&card("C2")));
+
+    game.table.discard = vec![card("J"); 3];
+    assert!(!game.is_card_dead(&card("J")));
+    game.table.discard(card("J"));
+    assert!(game.is_card_dead(&card("J")));
+}
+
+#[test]
+fn best_joker_placement() {
+    let rules = ScoringRules::default();
+
+    let hand = Hand(cards(&["H5", "J", "H7", "S2", "D2", "CQ"]));
+    let (run, position) = hand.best_joker_placement(&rules).unwrap();
+    assert_eq!(run, verify_run(cards(&["H5", "H7", "J"])).unwrap());
+    assert_eq!(position, 1);
+
+    let (run, position) = Hand(cards(&["J", "DQ", "DK", "S2"])).best_joker_placement(&rules).unwrap();
+    assert_eq!(run.to_display_cards(&RunCoercionStrategy::default()), cards(&["DJ", "DQ", "DK"]));
+    assert_eq!(position, 0);
+
+    assert_eq!(Hand(cards(&["H5", "H6", "H7"])).best_joker_placement(&rules), None);
+    assert_eq!(Hand(cards(&["H5", "J", "S9"])).best_joker_placement(&rules), None);
+}
+
+#[test]
+fn frozen_discard() {
+    let mut table = Table { stock: vec![card("S2")], freeze_on_joker: true, ..Table::default() };
+    table.discard(card("H5"));
+    assert!(!table.frozen);
+
+    table.discard(card("J"));
+    assert!(table.frozen && table.public_view().frozen);
+    assert_eq!(table.draw_from_discard(), None);
+    assert_eq!(table.discard_len(), 2);
+
+    table.discard(card("D9"));
+    assert_eq!(table.draw_from_discard(), None);
+
+    // turning the pile over thaws it, as long as there's no joker left on top
+    table.stock.clear();
+    assert!(table.replenish_stock());
+    assert!(!table.frozen);
+    assert_eq!(table.draw_from_discard(), Some(card("D9")));
+
+    let mut thawed = Table { freeze_on_joker: false, ..Table::default() };
+    thawed.discard(card("J"));
+    assert!(!thawed.frozen);
+
+    let config = GameConfig { jokers_freeze_discard: true, ..GameConfig::default() };
+    let mut game = Game::from_deck(generate_deck(false), 2, &config).unwrap();
+    game.apply(Move::DrawStock).unwrap();
+    game.players[game.current_player].hand.0.push(card("J"));
+    game.apply(Move::Discard(card("J"))).unwrap();
+    assert!(game.table.frozen);
+    assert!(!game.legal_moves().contains(&Move::DrawDiscard));
+    assert_eq!(game.apply(Move::DrawDiscard), Err(MoveError::FrozenPile));
+}
+
+#[test]
+fn hand_sets_and_sequences() {
+    let hand = Hand(cards(&["H5", "H6", "H7", "S9", "D9", "C9", "CK"]));
+
+    let sets = hand.sets();
+    let sequences = hand.sequences();
+    assert_eq!(sets, vec![verify_run(cards(&["S9", "D9", "C9"])).unwrap()]);
+    assert_eq!(sequences, vec![verify_run(cards(&["H5", "H6", "H7"])).unwrap()]);
+    assert!(sets.iter().all(|r| matches!(r, Run::Equal(_))));
+    assert!(sequences.iter().all(|r| matches!(r, Run::Ascending(_))));
+    assert_eq!(sets.len() + sequences.len(), hand.find_melds().len());
+}
+
+#[test]
+fn discard_to_go_out() {
+    let discarding = GameConfig::default();
+    let melding = GameConfig { discard_to_go_out: false, ..GameConfig::default() };
+
+    // thirteen cards that meld completely, so one of them can go
+    let complete = Hand(cards(&["S2", "S3", "S4", "S5", "D5", "D6", "D7", "H9", "C9", "D9", "SK", "HK", "DK"]));
+    assert!(complete.can_go_out_with(&discarding) && complete.can_go_out_with(&melding));
+    assert_eq!(complete.closing_discards(), cards(&["S2", "S5"]));
+
+    // twelve melded cards and one to discard
+    let closing = Hand(cards(&["S2", "S3", "S4", "D5", "D6", "D7", "H9", "C9", "D9", "SK", "HK", "DK", "CQ"]));
+    assert!(closing.can_go_out_with(&discarding) && closing.can_go_out_with(&melding));
+
+    // thirteen cards that don't meld
+    let stuck = Hand(cards(&["S2", "S3", "S4", "D5", "D6", "D7", "H9", "C9", "D9", "SK", "HK", "CQ", "H2"]));
+    assert!(!stuck.can_go_out_with(&discarding) && !stuck.can_go_out_with(&melding));
+
+    // melds completely, but leaves nothing to discard
+    let exact = Hand(cards(&["S2", "S3", "S4", "D5", "D6", "D7", "H9", "C9", "D9", "SK", "HK", "DK"]));
+    assert!(!exact.can_go_out_with(&discarding));
+    assert!(exact.can_go_out_with(&melding));
+
+    let mut game = Game::from_deck(generate_single_deck(false), 2, &melding).unwrap();
+    game.apply(Move::DrawStock).unwrap();
+    let current = game.current_player;
+    rig_hand(&mut game, current, &exact.0);
+    game.players[game.current_player].melds = vec![0];
+    for &c in cards(&["C4", "C5", "C6"]).iter() { assert!(swap_out(&mut game, usize::MAX, c, None)); }
+    game.table.melds.push(verify_run(cards(&["C4", "C5", "C6"])).unwrap());
+    let runs = exact.best_partition().0;
+    for run in runs.iter().take(3) { game.apply(Move::Meld(vec![run.clone()])).unwrap(); }
+    assert!(game.legal_moves().contains(&Move::Meld(vec![runs[3].clone()])));
+    game.apply(Move::Meld(vec![runs[3].clone()])).unwrap();
+    assert!(game.players[game.current_player].hand.0.is_empty());
+}
+
+#[test]
+fn run_card_budget() {
+    assert_eq!(verify_run_opts(cards(&["SA", "SA", "SA"]), Some(2)), Err(RunError::OverBudget(card("SA"))));
+    assert_eq!(verify_run(cards(&["SA", "SA", "SA"])), Err(RunError::DuplicateCard(card("SA"))));
+
+    let jokers = cards(&["S2", "S3", "J", "J", "J", "J", "J"]);
+    assert_eq!(verify_run_opts(jokers.clone(), Some(2)), Err(RunError::OverBudget(card("J"))));
+    assert!(verify_run_opts(jokers.clone(), Some(3)).is_ok());
+    assert!(verify_run(jokers).is_ok());
+
+    let valid = cards(&["H5", "H6", "J"]);
+    assert_eq!(verify_run_opts(valid.clone(), Some(1)), verify_run(valid));
+}
+
+#[test]
+fn information_set_size() {
+    // two hands of thirteen, one card on the discard pile and three in the stock
+    let deck: Vec<Card> = generate_deck(false).into_iter().take(30).collect();
+    let mut game = Game::deal(deck, 2).unwrap();
+    assert_eq!(game.information_set_size(0), 560); // C(16, 13)
+    assert_eq!(game.information_set_size(1), 560);
+
+    // player 1 takes the discard, so player 0 knows one of their cards
+    let taken = *game.table.peek_discard().unwrap();
+    game.apply(Move::DrawDiscard).unwrap();
+    assert_eq!(game.information_set_size(0), 560);
+
+    // and still knows it after they discard something else: C(15, 12)
+    let other = *game.players[1].hand.0.iter().find(|&&c| c != taken).unwrap();
+    game.apply(Move::Discard(other)).unwrap();
+    assert_eq!(game.information_set_size(0), 455);
+    assert_eq!(game.information_set_size(1), 560);
+
+    let full = new_game(4, &GameConfig::default(), 3).unwrap();
+    assert_eq!(full.information_set_size(0), u128::MAX);
+}
+
+#[test]
+fn clone_rng_state() {
+    use rand::Rng;
+
+    let mut game = new_game(2, &GameConfig::default(), 11).unwrap();
+    let _: u64 = game.rng.gen();
+
+    let mut clone = game.clone();
+    let mut fork = game.fork(1);
+    for m in [Move::DrawStock, Move::Discard(game.players[1].hand.0[0])] {
+        game.apply(m.clone()).unwrap();
+        clone.apply(m).unwrap();
+    }
+    assert_eq!(game.fingerprint(), clone.fingerprint());
+
+    // the clone continues the same stream, the fork doesn't
+    let original: Vec<u64> = (0..4).map(|_| game.rng.gen()).collect();
+    let cloned: Vec<u64> = (0..4).map(|_| clone.rng.gen()).collect();
+    assert_eq!(original, cloned);
+
+    let mut again = new_game(2, &GameConfig::default(), 11).unwrap();
+    let _: u64 = again.rng.gen();
+    let mut again = again.fork(1);
+    let forked: Vec<u64> = (0..4).map(|_| fork.rng.gen()).collect();
+    assert_ne!(forked[..], original[..]);
+    assert_eq!(forked, (0..4).map(|_| again.rng.gen()).collect::<Vec<u64>>());
+}
+
+#[test]
+fn perfect_close() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+    let runs = vec![run(&["S2", "S3", "S4"]), run(&["D5", "D6", "D7"]), run(&["H9", "C9", "D9"]), run(&["SK", "HK", "DK"])];
+
+    let mut config = GameConfig::default();
+    config.scoring.perfect_close_bonus = 50;
+    let setup = |config: &GameConfig| {
+        let mut game = Game::from_deck(generate_single_deck(false), 2, config).unwrap();
+        game.apply(Move::DrawStock).unwrap();
+        rig_hand(&mut game, 1, &cards(&["S2", "S3", "S4", "D5", "D6", "D7", "H9", "C9", "D9", "SK", "HK", "DK", "CQ"]));
+        game.players[1].score = 200;
+        game
+    };
+
+    // everything melded in one go
+    let mut game = setup(&config);
+    game.apply(Move::Meld(runs.clone())).unwrap();
+    game.apply(Move::Discard(card("CQ"))).unwrap();
+    assert!(game.is_perfect_close(1));
+    assert!(!game.is_perfect_close(0));
+    game.score_round();
+    assert_eq!(game.players[1].score, 150);
+
+    // having laid down a meld on an earlier turn doesn't count
+    let mut game = setup(&config);
+    game.events.insert(0, GameEvent::Meld { player: 1, runs: vec![run(&["C4", "C5", "C6"])] });
+    game.apply(Move::Meld(runs)).unwrap();
+    game.apply(Move::Discard(card("CQ"))).unwrap();
+    assert!(!game.is_perfect_close(1));
+    game.score_round();
+    assert_eq!(game.players[1].score, 200);
+}
+
+#[test]
+fn discard_notation() {
+    let mut table = Table::default();
+    assert_eq!(table.discard_notation(), "");
+
+    for notation in ["S2", "HX", "J", "DA", "CK"] { table.discard(card(notation)); }
+    assert_eq!(table.discard_notation(), "S2 HX J DA CK");
+
+    for card in generate_single_deck(false) {
+        assert_eq!(Card::try_from(card.to_notation().as_str()), Ok(card));
+    }
+}
+
+#[test]
+fn mandatory_move() {
+    let closing = Hand(cards(&["S2", "S3", "S4", "D5", "D6", "D7", "H9", "C9", "D9", "SK", "HK", "DK", "CQ"]));
+
+    let config = GameConfig { must_go_out: true, ..GameConfig::default() };
+    let mut game = Game::from_deck(generate_single_deck(false), 2, &config).unwrap();
+    assert_eq!(game.mandatory_move(), None);
+    game.apply(Move::DrawStock).unwrap();
+    rig_hand(&mut game, 1, &closing.0);
+
+    let mut moves = 0;
+    while let Some(m) = game.mandatory_move() {
+        assert!(game.legal_moves().contains(&m));
+        game.apply(m).unwrap();
+        moves += 1;
+    }
+    assert_eq!(moves, 2);
+    assert!(game.players[1].hand.0.is_empty());
+    assert_eq!(game.table.peek_discard(), Some(&card("CQ")));
+
+    // without the flag, or without a way out, nothing is forced
+    let mut free = Game::from_deck(generate_single_deck(false), 2, &GameConfig::default()).unwrap();
+    free.apply(Move::DrawStock).unwrap();
+    rig_hand(&mut free, 1, &closing.0);
+    assert_eq!(free.mandatory_move(), None);
+
+    let mut stuck = Game::from_deck(generate_single_deck(false), 2, &config).unwrap();
+    stuck.apply(Move::DrawStock).unwrap();
+    rig_hand(&mut stuck, 1, &cards(&["S2", "S3", "S4", "H9", "CQ"]));
+    assert_eq!(stuck.mandatory_move(), None);
+}
+
+#[test]
+fn smart_shuffle() {
+    // the most cards of any one suit in each of the first four hands dealt
+    let clumping = |mut deck: Vec<Card>| -> Vec<usize> {
+        (0..4).map(|_| {
+            let hand = Hand::draw(&mut deck);
+            hand.group_by_suit().into_iter().filter(|(s, _)| *s != Suit::JokerSuit).map(|(_, c)| c.len()).max().unwrap()
+        }).collect()
+    };
+
+    let (mut naive, mut smart) = (0, 0);
+    for seed in 0..200 {
+        let deck = generate_deck_smart_shuffle(seed);
+        crate::utils::assert_deck_conservation(&generate_deck(false), &[&deck]);
+
+        let clumps = clumping(deck);
+        assert!(clumps.iter().all(|&n| n <= 5));
+        smart += clumps.iter().sum::<usize>();
+        naive += clumping(generate_seeded_deck(seed, &GameConfig::default())).iter().sum::<usize>();
+    }
+
+    assert!(smart < naive);
+    assert!((smart as f64) / 800.0 < 4.75);
+}
+
+#[test]
+fn projected_hand_sizes() {
+    let mut game = new_game(3, &GameConfig::default(), 5).unwrap();
+    assert_eq!(game.projected_hand_sizes(), vec![13, 13, 13]);
+
+    // the drawn card is as good as gone again
+    game.apply(Move::DrawStock).unwrap();
+    assert_eq!(game.players[1].hand.0.len(), 14);
+    assert_eq!(game.projected_hand_sizes(), vec![13, 13, 13]);
+
+    // drawing and discarding turn after turn leaves the sizes as projected
+    let projected = game.projected_hand_sizes();
+    for _ in 0..6 {
+        if game.phase == TurnPhase::Draw { game.apply(Move::DrawStock).unwrap(); }
+        let card = game.players[game.current_player].hand.0[0];
+        game.apply(Move::Discard(card)).unwrap();
+    }
+    assert_eq!(game.players.iter().map(|p| p.hand.0.len()).collect::<Vec<_>>(), projected);
+}
+
+#[test]
+fn check_table_against_dealt_deck() {
+    let config = GameConfig { scoring: ScoringRules::for_deck_count(3), ..GameConfig::default() };
+    let mut game = Game::from_deck(generate_deck_with_decks(3, true), 2, &config).unwrap();
+
+    // the table holds three copies of most cards, which two decks would have no room for
+    for _ in 0..20 {
+        game.apply(Move::DrawStock).unwrap();
+        let card = game.players[game.current_player].hand.0[0];
+        game.apply(Move::Discard(card)).unwrap();
+    }
+    assert_eq!(game.table.validate_against(&game.deck), Ok(()));
+    assert!(game.table.validate().is_err());
+
+    // scoring rules for a single deck, but dealt from two
+    let config = GameConfig { scoring: ScoringRules::for_deck_count(1), ..GameConfig::default() };
+    let mut game = Game::deal(generate_deck(false), 2).unwrap();
+    game.config = config;
+    game.apply(Move::DrawStock).unwrap();
+    assert_eq!(game.deck.len(), FULL_DECK_SIZE);
+
+    // more jokers to a deck than validate() allows for
+    let config = GameConfig { jokers_per_deck: 4, ..GameConfig::default() };
+    let mut game = Game::from_deck(generate_deck_with(false, &config), 2, &config).unwrap();
+    game.apply(Move::DrawStock).unwrap();
+    game.table.stock.retain(|c| c.card_type != CardType::Joker);
+    game.table.stock.extend([card("J"); 8]);
+    assert_eq!(game.table.validate_against(&game.deck), Ok(()));
+    assert_eq!(game.table.validate(), Err(vec![TableError::OverBudget(card("J"))]));
+    game.table.stock.push(card("J"));
+    assert_eq!(game.table.validate_against(&game.deck), Err(vec![TableError::OverBudget(card("J"))]));
+}
+
+#[test]
+fn validate_table() {
+    let mut table = Game::deal(generate_deck(false), 2).unwrap().table;
+    assert_eq!(table.validate(), Ok(()));
+
+    // lay down a meld without taking its cards off of the stock, which holds both copies
+    table.melds.push(verify_run(cards(&["S4", "S5", "S6"])).unwrap());
+    let overlap = cards(&["S4", "S5", "S6"]).into_iter().map(TableError::Overlap).collect();
+    assert_eq!(table.validate(), Err(overlap));
+
+    let mut pile = Table { stock: cards(&["HK", "HK", "HK"]), ..Table::default() };
+    assert_eq!(pile.validate(), Err(vec![TableError::OverBudget(card("HK"))]));
+    assert_eq!(pile.validate_against(&generate_deck_with_decks(3, false)), Ok(()));
+
+    pile.stock.clear();
+    pile.melds.push(Run::Ascending(cards(&["S2", "S3", "S5"])));
+    assert_eq!(pile.validate(), Err(vec![TableError::InvalidMeld { index: 0, error: RunError::Invalid }]));
+}
+
+#[test]
+fn optimal_defensive_discard() {
+    let mut game = Game::deal(generate_single_deck(false), 2).unwrap();
+    game.players[1].hand = Hand(cards(&["S2", "H3", "HK", "C7", "D8", "D9", "DX"]));
+    assert_eq!(game.optimal_defensive_discard(), Some(card("S2")));
+
+    // the two of spades would help the opponent extend their meld, the three of hearts wouldn't
+    game.table.melds.push(verify_run(cards(&["S4", "S5", "S6"])).unwrap());
+    game.players[0].melds.push(0);
+    assert_eq!(game.optimal_defensive_discard(), Some(card("H3")));
+
+    game.players[1].hand = Hand(Vec::new());
+    assert_eq!(game.optimal_defensive_discard(), None);
+}
+
+#[test]
+fn accounted_cards() {
+    let mut game = new_game(2, &GameConfig::default(), 8).unwrap();
+    for _ in 0..6 {
+        game.apply(Move::DrawStock).unwrap();
+        let player = game.current_player;
+        if let Some(meld) = game.legal_moves().into_iter().find(|m| matches!(m, Move::Meld(_))) { game.apply(meld).unwrap(); }
+        let card = game.players[player].hand.0[0];
+        game.apply(Move::Discard(card)).unwrap();
+    }
+
+    for player in 0..2 {
+        assert_eq!(game.verify_player(player), Ok(()));
+        let accounted = game.players[player].accounted_cards(&game.table);
+        assert_eq!(accounted.len(), game.players[player].hand.0.len()
+            + game.players[player].melds.iter().map(|&i| game.table.melds[i].cards().len()).sum::<usize>());
+    }
+
+    // a card vanishes from a hand
+    let vanished = game.players[0].hand.0.pop().unwrap();
+    assert_eq!(game.verify_player(0), Err(vec![(vanished, 1)]));
+    assert_eq!(game.verify_player(1), Ok(()));
+}
+
+#[test]
+fn max_meld_score() {
+    let rules = ScoringRules::default();
+
+    // the joker is worth the most as the seven, rather than the four
+    let hand = Hand(cards(&["H5", "H6", "J", "S9", "D9", "C9", "CK"]));
+    let run = verify_run(cards(&["H5", "H6", "J"])).unwrap();
+    assert_eq!((run.get_score(), run.max_score()), (15, 18));
+    assert_eq!(hand.max_meld_score(&rules), 18 + 27);
+    // a joker in a meld counts as the card it stands for, whatever a loose one is worth
+    assert_eq!(hand.max_meld_score(&ScoringRules { joker_value: 50, ..rules }), 18 + 27);
+
+    // a joker is worth more as a high ace than as the jack
+    assert_eq!(Hand(cards(&["SQ", "SK", "J"])).max_meld_score(&rules), 31);
+    assert_eq!(Hand(cards(&["DQ", "SQ", "J"])).max_meld_score(&rules), 30);
+    assert_eq!(Hand(cards(&["S2", "H7", "CK"])).max_meld_score(&rules), 0);
+}
+
+#[test]
+fn maximal_runs() {
+    let full_set = verify_run(cards(&["S7", "D7", "C7", "H7"])).unwrap();
+    assert!(full_set.legal_extensions().is_empty());
+    assert!(full_set.is_maximal());
+
+    let sequence = verify_run(cards(&["H5", "H6", "H7"])).unwrap();
+    assert_eq!(sequence.legal_extensions(), cards(&["H4", "H8", "J"]));
+    assert!(!sequence.is_maximal());
+
+    let set = verify_run(cards(&["S7", "J", "H7"])).unwrap();
+    assert_eq!(set.legal_extensions(), cards(&["D7", "C7", "J"]));
+
+    let whole_suit = verify_run(cards(&["DA", "D2", "D3", "D4", "D5", "D6", "D7", "D8", "D9", "DX", "DJ", "DQ", "DK", "J"])).unwrap();
+    assert!(whole_suit.is_maximal());
+}
+
+#[test]
+fn scoring_for_deck_count() {
+    let single = ScoringRules::for_deck_count(1);
+    let double = ScoringRules::for_deck_count(2);
+
+    assert_eq!(double, ScoringRules::default());
+    assert_eq!((single.deck_count, double.deck_count), (1, 2));
+    assert_eq!((single.full_hand_penalty, double.full_hand_penalty), (50, 100));
+    assert_eq!(single, ScoringRules { full_hand_penalty: 50, deck_count: 1, ..double });
+
+    let never_melded = Hand::draw(&mut generate_deck(false));
+    assert_eq!(never_melded.score_with(&single), 50);
+    assert_eq!(never_melded.score_with(&double), 100);
+}
+
+#[test]
+fn play_with_a_single_deck() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+    let config = GameConfig { scoring: ScoringRules::for_deck_count(1), ..GameConfig::default() };
+    let mut game = new_game(2, &config, 7).unwrap();
+    assert_eq!(game.deck.len(), SINGLE_DECK_SIZE);
+    assert_eq!(game.players.iter().map(|p| p.hand.0.len()).sum::<usize>() + game.table.stock.len() + game.table.discard_len(), SINGLE_DECK_SIZE);
+
+    rig_hand(&mut game, 1, &cards(&["D9", "DX", "DJ", "DQ", "S2", "H5"]));
+    rig_top(&mut game, DrawSource::Discard, "DK");
+    game.apply(Move::DrawDiscard).unwrap();
+    game.apply(Move::Meld(vec![run(&["D9", "DX", "DJ", "DQ", "DK"])])).unwrap();
+    game.apply(Move::Discard(card("H5"))).unwrap();
+
+    // there's only a single copy of every card, and two jokers
+    assert!(game.is_card_dead(&card("DK")));
+    assert!(game.is_card_dead(&card("H5")));
+    let held = game.players[0].hand.0.iter().filter(|c| c.card_type == CardType::Joker).count();
+    assert_eq!(game.jokers_unaccounted(0), 2 - held);
+    assert_eq!(game.players[1].hand.0, cards(&["S2"]));
+}
+
+#[test]
+fn hand_contains_all() {
+    let hand = Hand(cards(&["H5", "H6", "H7", "S9", "S9", "J"]));
+
+    assert!(hand.contains_all(&cards(&["H5", "H6", "H7"])));
+    assert!(hand.contains_all(&cards(&["S9", "J", "S9"])));
+    assert!(hand.contains_all(&[]));
+    assert!(!hand.contains_all(&cards(&["H5", "H6", "H8"])));
+
+    // one copy of a card doesn't cover two
+    assert!(!hand.contains_all(&cards(&["J", "J", "H5"])));
+    assert!(!hand.contains_all(&cards(&["S9", "S9", "S9"])));
+}
+
+#[test]
+fn expected_draws_to_complete() {
+    use std::collections::HashMap;
+    let unseen = |notation: &[(&str, u8)]| notation.iter().map(|&(s, n)| (card(s), n)).collect::<HashMap<_, _>>();
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+    // the ace is equally likely to be any of the four cards
+    let four = unseen(&[("SA", 1), ("H2", 1), ("D7", 1), ("CK", 1)]);
+    assert!(close(crate::utils::expected_draws_to_complete(&[card("SA")], &four, 1), 2.5));
+    assert!(close(crate::utils::expected_draws_to_complete(&[card("SA")], &four, 2), 1.5));
+
+    // the last of two cards out of three comes second a third of the time, and last otherwise
+    let three = unseen(&[("SA", 1), ("H2", 1), ("D7", 1)]);
+    assert!(close(crate::utils::expected_draws_to_complete(&[card("SA"), card("H2")], &three, 1), 8.0 / 3.0));
+
+    // both copies of a card out of three
+    let copies = unseen(&[("SA", 2), ("H2", 1)]);
+    assert!(close(crate::utils::expected_draws_to_complete(&[card("SA"), card("SA")], &copies, 1), 8.0 / 3.0));
+
+    assert_eq!(crate::utils::expected_draws_to_complete(&[], &four, 1), 0.0);
+    assert_eq!(crate::utils::expected_draws_to_complete(&[card("HK")], &four, 1), f64::INFINITY);
+    assert_eq!(crate::utils::expected_draws_to_complete(&[card("SA"), card("SA")], &four, 1), f64::INFINITY);
+}
+
+#[test]
+fn visible_jokers() {
+    let mut game = Game::deal(generate_deck(false), 2).unwrap();
+    game.table.melds.clear();
+    game.table.discard = cards(&["H4"]);
+    game.players[0].hand = Hand(cards(&["S2", "S3"]));
+    game.players[1].hand = Hand(cards(&["D9", "J"]));
+    assert_eq!(game.table.visible_jokers(), 0);
+    assert_eq!(game.jokers_unaccounted(0), 4);
+    assert_eq!(game.jokers_unaccounted(1), 3);
+
+    game.table.melds.push(verify_run(cards(&["H5", "H6", "J"])).unwrap());
+    game.table.discard.extend(cards(&["J", "CK"]));
+    assert_eq!(game.table.visible_jokers(), 2);
+    assert_eq!(game.jokers_unaccounted(0), 2);
+    assert_eq!(game.jokers_unaccounted(1), 1);
+
+    game.table.discard.extend(cards(&["J", "J"]));
+    assert_eq!(game.jokers_unaccounted(1), 0);
+}
+
+#[test]
+fn pretty_hand() {
+    let hand = Hand(cards(&["HK", "J", "S2", "H3", "SA", "HX", "SK", "J", "C7"]));
+    assert_eq!(hand.pretty(), "♠A ♠2 ♠K\n♣7\n♥3 ♥X ♥K\n🃏 🃏");
+    assert_eq!(Hand(cards(&["D5"])).pretty(), "♦5");
+    assert_eq!(Hand(Vec::new()).pretty(), "");
+}
+
+#[test]
+fn undo_meld() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+
+    let mut game = Game::deal(generate_single_deck(false), 2).unwrap();
+    game.apply(Move::DrawStock).unwrap();
+    rig_hand(&mut game, 1, &cards(&["S2", "S3", "S4", "HK", "DK", "CK", "SK", "H2", "D7"]));
+    let before = game.players[1].hand.0.len();
+
+    // open with two melds, then take one back: the other one is still good enough
+    game.apply(Move::Meld(vec![run(&["S2", "S3", "S4"]), run(&["HK", "DK", "CK", "SK"])])).unwrap();
+    assert_eq!(game.uncommitted_melds, vec![0, 1]);
+    assert_eq!(game.undo_meld(1), Err(MoveError::Opening(OpeningError::TooFewPoints { scored: 9 })));
+    assert_eq!(game.undo_meld(0), Ok(()));
+    assert_eq!(game.players[1].hand.0.len(), before - 4);
+    assert!(game.players[1].hand.contains_all(&cards(&["S2", "S3", "S4"])));
+    assert_eq!(game.table.melds, vec![run(&["HK", "DK", "CK", "SK"])]);
+    assert_eq!((game.players[1].melds.clone(), game.uncommitted_melds.clone()), (vec![0], vec![0]));
+    assert!(matches!(game.events.last(), Some(GameEvent::UndoMeld { player: 1, meld: 0, .. })));
+
+    // the whole opening can go back, too
+    assert_eq!(game.undo_meld(0), Ok(()));
+    assert_eq!(game.players[1].hand.0.len(), before);
+    assert!(game.players[1].melds.is_empty() && game.table.melds.is_empty());
+
+    // once the turn is committed, they're there to stay
+    game.apply(Move::Meld(vec![run(&["S2", "S3", "S4"]), run(&["HK", "DK", "CK", "SK"])])).unwrap();
+    game.apply(Move::Discard(card("D7"))).unwrap();
+    assert!(game.uncommitted_melds.is_empty());
+    game.apply(Move::DrawStock).unwrap();
+    assert_eq!(game.undo_meld(0), Err(MoveError::CannotUndo));
+    assert_eq!(game.table.melds.len(), 2);
+}
+
+#[test]
+fn benefit_from_discard() {
+    let rules = ScoringRules::default();
+    let hand = Hand(cards(&["H5", "H6", "S9", "CK"]));
+
+    // the seven of hearts completes a meld
+    let top = card("H7");
+    assert!(hand.would_benefit_from_discard(&top, &rules));
+    assert_eq!(hand.discard_benefit(&top, &rules), (18 - 19) - (0 - 30));
+
+    // the two of diamonds only adds to the deadwood
+    let top = card("D2");
+    assert!(!hand.would_benefit_from_discard(&top, &rules));
+    assert_eq!(hand.discard_benefit(&top, &rules), -2);
+}
+
+#[test]
+fn turn_plans() {
+    let run = |notation: &[&str]| verify_run(cards(notation)).unwrap();
+
+    let mut game = Game::deal(generate_single_deck(false), 2).unwrap();
+    rig_hand(&mut game, 1, &cards(&["HK", "DK", "CK", "SK", "S2", "S3", "S4", "D7"]));
+    rig_hand(&mut game, 0, &cards(&["H5", "H6", "C9"]));
+    let drawn = *game.table.stock.last().unwrap();
+
+    let legal = TurnPlan {
+        draw: DrawSource::Stock,
+        plays: vec![Move::Meld(vec![run(&["HK", "DK", "CK", "SK"])]), Move::Meld(vec![run(&["S2", "S3", "S4"])])],
+        discard: Some(card("D7"))
+    };
+    let fingerprint = game.fingerprint();
+    assert_eq!(game.validate_turn(&legal), Ok(()));
+    assert_eq!(game.fingerprint(), fingerprint);
+
+    // the second meld isn't valid, so nothing of the plan happens
+    let illegal = TurnPlan { plays: vec![legal.plays[0].clone(), Move::Meld(vec![Run::Ascending(cards(&["S2", "S3", "D7"]))])], ..legal.clone() };
+    assert!(matches!(game.validate_turn(&illegal), Err(MoveError::InvalidMeld(_))));
+    assert!(matches!(game.apply_turn(&illegal), Err(MoveError::InvalidMeld(_))));
+    assert_eq!(game.fingerprint(), fingerprint);
+    assert_eq!(game.players[1].hand.0.len(), 8);
+    assert!(game.table.melds.is_empty());
+
+    assert_eq!(game.apply_turn(&legal), Ok(()));
+    assert_eq!(game.players[1].hand.0, vec![drawn]);
+    assert_eq!(game.table.melds.len(), 2);
+    assert_eq!((game.current_player, game.phase), (0, TurnPhase::Draw));
+
+    let drawing = TurnPlan { draw: DrawSource::Stock, plays: vec![Move::DrawDiscard], discard: None };
+    assert_eq!(game.validate_turn(&drawing), Err(MoveError::WrongPhase));
+}
+
+#[test]
+fn discard_rle() {
+    let table = Table { discard: cards(&["S2", "S2", "HK", "J", "J", "J", "S2"]), ..Table::default() };
+    let rle = table.discard_rle();
+    assert_eq!(rle, vec![(card("S2"), 2), (card("HK"), 1), (card("J"), 3), (card("S2"), 1)]);
+    assert_eq!(Table::expand_discard_rle(&rle), table.discard);
+
+    let long = Table { discard: vec![card("D4"); 300], ..Table::default() };
+    assert_eq!(long.discard_rle(), vec![(card("D4"), 255), (card("D4"), 45)]);
+    assert_eq!(Table::expand_discard_rle(&long.discard_rle()), long.discard);
+
+    assert!(Table::default().discard_rle().is_empty());
+}
+
+#[test]
+fn hand_flexibility() {
+    use std::collections::HashMap;
+    let mut unseen: HashMap<Card, u8> = generate_single_deck(false).into_iter().map(|c| (c, 1)).collect();
+
+    // every gap can be filled by its own card or by a joker
+    let gaps = Hand(cards(&["H5", "H7", "S9", "SJ", "D2", "D4"]));
+    assert_eq!(gaps.flexibility(&unseen), 4);
+
+    let rigid = Hand(cards(&["S2", "H7", "DK", "C5"]));
+    assert_eq!(rigid.flexibility(&unseen), 0);
+
+    unseen.insert(card("H6"), 0);
+    assert_eq!(gaps.flexibility(&unseen), 3);
+}
+
+#[test]
+fn round_winner() {
+    let config = GameConfig { must_go_out: true, ..GameConfig::default() };
+    let mut game = Game::from_deck(generate_single_deck(false), 3, &config).unwrap();
+    assert_eq!(game.round_winner(), None);
+
+    // the player after the current one can go out as soon as it's their turn
+    rig_hand(&mut game, 2, &cards(&["S2", "S3", "S4", "D5", "D6", "D7", "H9", "C9", "D9", "SK", "HK", "DK"]));
+    while game.round_winner().is_none() {
+        game.apply(Move::DrawStock).unwrap();
+        match game.mandatory_move() {
+            Some(m) => game.apply(m).unwrap(),
+            None => {
+                let card = game.players[game.current_player].hand.0[0];
+                game.apply(Move::Discard(card)).unwrap();
+            }
+        }
+        while let Some(m) = game.mandatory_move() { game.apply(m).unwrap(); }
+    }
+    assert_eq!(game.round_winner(), Some(2));
+
+    // nobody gets to move once the round is over, not even the player who went out
+    let card = game.players[0].hand.0[0];
+    assert_eq!((game.current_player, game.phase), (2, TurnPhase::Draw));
+    assert_eq!(game.apply(Move::DrawStock), Err(MoveError::RoundOver));
+    game.phase = TurnPhase::Play;
+    game.current_player = 0;
+    assert_eq!(game.apply(Move::Discard(card)), Err(MoveError::RoundOver));
+    assert_eq!(game.legal_moves(), vec![]);
+    assert_eq!(game.mandatory_move(), None);
+
+    let mut stalemate = Game::from_deck(generate_single_deck(false), 2, &GameConfig::default()).unwrap();
+    stalemate.table.stock.clear();
+    assert!(stalemate.is_stalemate());
+    assert_eq!(stalemate.round_winner(), None);
+}
+
+#[test]
+fn match_standings() {
+    let mut game = Game::deal(generate_single_deck(false), 3).unwrap();
+
+    // three rounds, each scored by the hands that are left
+    let rounds = [
+        [vec![], cards(&["HK", "S2"]), cards(&["D5"])],
+        [cards(&["CX", "SQ"]), vec![], cards(&["H3", "H9"])],
+        [cards(&["DK", "HQ", "C7"]), cards(&["S4"]), vec![]]
+    ];
+    let mut over = Vec::new();
+    for hands in rounds {
+        for (player, hand) in hands.into_iter().enumerate() { game.players[player].hand = Hand(hand); }
+        game.score_round();
+        over.push(game.match_over(40));
+    }
+
+    assert_eq!(game.standings(), vec![(1, 12 + 4), (2, 5 + 12), (0, 20 + 27)]);
+    assert_eq!(over, vec![None, None, Some(1)]);
+    assert_eq!(game.match_over(100), None);
+}
+
+#[test]
+fn generate_deck_without() {
+    let ace = card("SA");
+
+    let deck = crate::utils::generate_deck_without(&[ace, ace], true);
+    assert_eq!(deck.len(), FULL_DECK_SIZE - 2);
+    assert!(!deck.contains(&ace));
+
+    let deck = crate::utils::generate_deck_without(&[ace, card("J")], false);
+    assert_eq!(deck.iter().filter(|&&c| c == ace).count(), 1);
+    assert_eq!(crate::utils::validate_deck(&deck, 2), Err(vec![(ace, 1), (card("J"), 1)]));
+
+    assert_eq!(crate::utils::generate_deck_without(&[ace, ace, ace], false).len(), FULL_DECK_SIZE - 2);
 }
@@ -1,12 +1,22 @@
 //! Handy functions
 
+use crate::{SINGLE_DECK_SIZE, HAND_SIZE};
 use crate::card::{Card, Suit::*, CardType::*};
-use rand::{thread_rng, seq::SliceRandom};
+use crate::rules::GameConfig;
+use std::collections::HashMap;
+use rand::{thread_rng, rngs::StdRng, seq::SliceRandom, SeedableRng};
 /// Generates a deck of 52 normal cards and 2 jokers in standard order.
 ///
 /// If shuffled is true, shuffles the deck before returning it.
 pub fn generate_single_deck(shuffled: bool) -> Vec<Card> {
-    let mut deck: Vec<Card> = Vec::new();
+    generate_single_deck_with(shuffled, &GameConfig::default())
+}
+
+/// Generates a deck of 52 normal cards and `config.jokers_per_deck` jokers in standard order.
+///
+/// If shuffled is true, shuffles the deck before returning it.
+pub fn generate_single_deck_with(shuffled: bool, config: &GameConfig) -> Vec<Card> {
+    let mut deck: Vec<Card> = Vec::with_capacity(SINGLE_DECK_SIZE);
 
     for suit in [Spades, Hearts, Diamonds, Clubs] {
         deck.push(Card::new(Ace, suit));
@@ -16,7 +26,7 @@ pub fn generate_single_deck(shuffled: bool) -> Vec<Card> {
         for i in 2..11 { deck.push(Card::new(Number(i), suit)); }
     }
 
-    for _ in 0..2 { deck.push(Card::new(Joker, JokerSuit)) }
+    for _ in 0..config.jokers_per_deck { deck.push(Card::new(Joker, JokerSuit)) }
 
     if shuffled {
         deck.shuffle(&mut thread_rng());
@@ -27,8 +37,217 @@ pub fn generate_single_deck(shuffled: bool) -> Vec<Card> {
 
 /// Generates the full playing deck, consisting of two normal decks.
 pub fn generate_deck(shuffled: bool) -> Vec<Card> {
-    let mut deck = generate_single_deck(shuffled);
-    deck.append(&mut generate_single_deck(shuffled));
+    generate_deck_with(shuffled, &GameConfig::default())
+}
+
+/// Generates the full playing deck, consisting of `config.scoring.deck_count`
+/// decks (two by default) as generated by generate_single_deck_with().
+pub fn generate_deck_with(shuffled: bool, config: &GameConfig) -> Vec<Card> {
+    let mut deck = Vec::with_capacity(usize::from(config.scoring.deck_count) * SINGLE_DECK_SIZE);
+    for _ in 0..config.scoring.deck_count { deck.append(&mut generate_single_deck_with(shuffled, config)); }
+
+    deck
+}
+
+/// Generates a playing deck made of `decks` single decks (see
+/// generate_single_deck()), which comes down to two jokers per single deck.
+///
+/// If shuffled is true, shuffles the whole deck before returning it.
+pub fn generate_deck_with_decks(decks: usize, shuffled: bool) -> Vec<Card> {
+    let mut deck = Vec::with_capacity(decks * SINGLE_DECK_SIZE);
+    for _ in 0..decks { deck.append(&mut generate_single_deck(false)); }
+
+    if shuffled {
+        deck.shuffle(&mut thread_rng());
+    }
+
+    deck
+}
+
+/// Generates the full playing deck (see generate_deck()) with the given cards
+/// taken out, e.g. to set up a puzzle. Every card in `excluded` takes out one
+/// copy, so a card has to be listed twice to take out both of its copies;
+/// cards the deck doesn't hold (anymore) are ignored.
+///
+/// If shuffled is true, shuffles the deck before returning it.
+pub fn generate_deck_without(excluded: &[Card], shuffled: bool) -> Vec<Card> {
+    let mut deck = generate_deck(shuffled);
+    for card in excluded {
+        if let Some(index) = deck.iter().position(|c| c == card) { deck.remove(index); }
+    }
 
     deck
 }
+
+/// Checks that the given deck holds exactly the cards of `decks` single decks
+/// (see generate_deck_with_decks()), in any order.
+///
+/// If it doesn't, returns every card whose count is off, along with how many
+/// copies are missing (a negative count means there are copies too many).
+pub fn validate_deck(deck: &[Card], decks: usize) -> Result<(), Vec<(Card, isize)>> {
+    let mismatched = count_mismatches(&generate_deck_with_decks(decks, false), &[deck]);
+    if mismatched.is_empty() { Ok(()) } else { Err(mismatched) }
+}
+
+/// Checks that the given cards could all have come out of `decks` single decks
+/// (see generate_deck_with_decks()), i.e. that no card appears more often than
+/// it does in there. Unlike validate_deck(), the cards don't have to make up
+/// the whole deck.
+///
+/// If they couldn't have, returns every card that appears too often.
+pub fn validate_card_budget(cards: &[Card], decks: usize) -> Result<(), Vec<Card>> {
+    let over: Vec<Card> = count_mismatches(&generate_deck_with_decks(decks, false), &[cards]).into_iter()
+        .filter(|&(_, n)| n < 0)
+        .map(|(card, _)| card)
+        .collect();
+
+    if over.is_empty() { Ok(()) } else { Err(over) }
+}
+
+/// Generates the full playing deck according to the given config (see
+/// generate_deck_with()), shuffled deterministically by the given seed.
+pub fn generate_seeded_deck(seed: u64, config: &GameConfig) -> Vec<Card> {
+    let mut deck = generate_deck_with(false, config);
+    deck.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    deck
+}
+
+/// The most cards of a single suit that generate_deck_smart_shuffle() lets a
+/// dealt hand hold.
+const SMART_SHUFFLE_SUIT_CAP: usize = 5;
+
+/// Generates the full playing deck according to the default config, shuffled
+/// deterministically by the given seed (see generate_seeded_deck()), after
+/// which an anti-clumping pass makes sure no dealt hand holds a lot of cards of
+/// the same suit. Plain shuffling stays the default, since this isn't uniform.
+///
+/// The pass looks at the deck in windows of HAND_SIZE cards, starting from the
+/// top (the end), which is how hands are dealt from it (see Hand::draw()). As
+/// long as a window holds more than five cards of a suit, the topmost of those
+/// is swapped with the nearest card below the window of a suit that the window
+/// holds fewer than five cards of. Jokers are never moved, and windows that
+/// have been looked at are never touched again, so the pass ends once it runs
+/// out of whole windows. The deeper windows have fewer cards to swap with, so
+/// if a suit can't be fixed, that window is left as it is.
+pub fn generate_deck_smart_shuffle(seed: u64) -> Vec<Card> {
+    let mut deck = generate_seeded_deck(seed, &GameConfig::default());
+    let hand = usize::from(HAND_SIZE);
+    let count = |cards: &[Card], suit| cards.iter().filter(|c| c.suit == suit).count();
+
+    let mut end = deck.len();
+    while end >= hand {
+        let start = end - hand;
+
+        while let Some(i) = (start..end).rev().find(|&i| deck[i].suit != JokerSuit && count(&deck[start..end], deck[i].suit) > SMART_SHUFFLE_SUIT_CAP) {
+            let window = &deck[start..end];
+            let swap = (0..start).rev().find(|&j| deck[j].suit != JokerSuit && count(window, deck[j].suit) < SMART_SHUFFLE_SUIT_CAP);
+            match swap {
+                Some(j) => deck.swap(i, j),
+                None => break
+            }
+        }
+
+        end = start;
+    }
+
+    deck
+}
+
+/// Takes `n` cards off the top (the end) of the deck, in the order they're
+/// drawn. If the deck holds fewer than `n` cards, it's left untouched and the
+/// amount of cards it does hold is returned instead.
+pub fn take_cards(deck: &mut Vec<Card>, n: usize) -> Result<Vec<Card>, usize> {
+    if deck.len() < n { return Err(deck.len()); }
+
+    Ok(deck.drain(deck.len() - n..).rev().collect())
+}
+
+/// Estimates how many turns of drawing it takes until every one of the missing
+/// cards has been drawn, drawing `draws_per_turn` cards a turn, from a stock
+/// that holds the unseen cards (along with how many copies of each) in random
+/// order. With one draw per turn, that's the expected amount of draws. A card
+/// that's missing more than once needs that many copies. Returns zero when
+/// nothing is missing, and infinity if the unseen cards don't cover what's missing.
+///
+/// ## Notes
+/// Every draw is assumed to come from the stock, nothing is picked up from the
+/// discard pile, and every unseen card is assumed to be in the stock (rather
+/// than in someone's hand). Under those assumptions, the amount of the missing
+/// cards among the first t draws follows a multivariate hypergeometric
+/// distribution, so the chance P(T > t) that they haven't all turned up yet is
+/// counted exactly, and the expectation is the sum of P(T > m · draws_per_turn)
+/// over every turn m.
+pub fn expected_draws_to_complete(missing: &[Card], unseen: &HashMap<Card, u8>, draws_per_turn: usize) -> f64 {
+    if missing.is_empty() { return 0.0; }
+
+    let mut needed: HashMap<Card, usize> = HashMap::new();
+    for &card in missing { *needed.entry(card).or_default() += 1; }
+
+    let total: usize = unseen.values().map(|&n| usize::from(n)).sum();
+    let binomials = |n: usize| (0..=n).scan(1.0, move |c: &mut f64, k| {
+        let value = *c;
+        *c = *c * (n - k) as f64 / (k + 1) as f64;
+        Some(value)
+    }).collect::<Vec<f64>>();
+
+    // ways[t] holds the amount of t-card draws that include every missing card
+    let mut rest = total;
+    let mut ways = vec![1.0];
+    for (card, &need) in needed.iter() {
+        let copies = unseen.get(card).map_or(0, |&n| usize::from(n));
+        if copies < need { return f64::INFINITY; }
+        rest -= copies;
+
+        let factor: Vec<f64> = binomials(copies).into_iter().enumerate().map(|(j, c)| if j < need { 0.0 } else { c }).collect();
+        ways = convolve(&ways, &factor);
+    }
+    let ways = convolve(&ways, &binomials(rest));
+    let all = binomials(total);
+
+    let draws_per_turn = draws_per_turn.max(1);
+    (0..total.div_ceil(draws_per_turn))
+        .map(|m| 1.0 - ways[m * draws_per_turn] / all[m * draws_per_turn])
+        .sum()
+}
+
+/// Multiplies two polynomials, given by their coefficients, lowest first.
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut product = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() { product[i + j] += x * y; }
+    }
+
+    product
+}
+
+/// Calculates the minimum amount of cards a deck needs to seat the given
+/// amount of players: a hand for every player, one card to start the discard
+/// pile with and at least one card to leave in the stock.
+pub fn min_deck_for(players: usize, hand_size: usize) -> usize {
+    players * hand_size + 2
+}
+
+/// Asserts that the given parts together hold exactly the cards of the
+/// original deck, e.g. after dealing the deck into hands and a stock.
+///
+/// Panics with the offending cards if that isn't the case. A positive count
+/// means copies of that card went missing, a negative count means there are
+/// copies too many.
+pub fn assert_deck_conservation(original: &[Card], parts: &[&[Card]]) {
+    let mismatched = count_mismatches(original, parts);
+    assert!(mismatched.is_empty(), "the cards in the deck were not conserved: {:?}", mismatched);
+}
+
+/// Counts how many copies of every card in the original are missing from the
+/// parts taken together, and returns the cards for which that isn't zero.
+pub(crate) fn count_mismatches(original: &[Card], parts: &[&[Card]]) -> Vec<(Card, isize)> {
+    let mut counts = [0isize; 53];
+    for card in original { counts[card.to_id() as usize] += 1; }
+    for card in parts.iter().flat_map(|part| part.iter()) { counts[card.to_id() as usize] -= 1; }
+
+    counts.iter().enumerate()
+        .filter(|(_, &n)| n != 0)
+        .map(|(id, &n)| (Card::from_id(id as u8).unwrap(), n))
+        .collect()
+}